@@ -0,0 +1,198 @@
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::error::{Error, Result};
+
+// Built-in alphabets in the same format as the custom alphabet files.
+// Every whitespace separated token is one letter, several characters in a
+// token are spellings of the same letter (e.g. Greek final sigma).
+const BUILTIN_SOURCES: [(&str, &str); 10] = [
+    (
+        "ru",
+        "name = Русский
+         а б в г д е ё ж з и й к л м н о п р с т у ф х ц ч ш щ ъ ы ь э ю я",
+    ),
+    (
+        "en",
+        "name = Латиница
+         a b c d e f g h i j k l m n o p q r s t u v w x y z",
+    ),
+    (
+        "uk",
+        "name = Украинский
+         а б в г ґ д е є ж з и і ї й к л м н о п р с т у ф х ц ч ш щ ь ю я",
+    ),
+    (
+        "be",
+        "name = Белорусский
+         а б в г д е ё ж з і й к л м н о п р с т у ў ф х ц ч ш ы ь э ю я",
+    ),
+    (
+        "kk",
+        "name = Казахский
+         а ә б в г ғ д е ё ж з и й к қ л м н ң о ө п р с т у ұ ү ф х һ ц ч ш щ ъ ы і ь э ю я",
+    ),
+    (
+        "de",
+        "name = Немецкий
+         a b c d e f g h i j k l m n o p q r s t u v w x y z ä ö ü ß",
+    ),
+    (
+        "pl",
+        "name = Польский
+         a ą b c ć d e ę f g h i j k l ł m n ń o ó p r s ś t u w y z ź ż",
+    ),
+    (
+        "cu",
+        "name = Церковнославянский
+         а б в г д еє ж ѕ з и ії к л м н о п р с т у ф х ѡ ц ч ш щ ъ ы ь ѣ ю ѧ ѯ ѱ ѳ ѵ",
+    ),
+    (
+        "el",
+        "name = Греческий
+         α β γ δ ε ζ η θ ι κ λ μ ν ξ ο π ρ σς τ υ φ χ ψ ω",
+    ),
+    (
+        "he",
+        "name = Иврит
+         א ב ג ד ה ו ז ח ט י כך ל מם נן ס ע פף צץ ק ר ש ת",
+    ),
+];
+
+static BUILTIN: LazyLock<Vec<Alphabet>> = LazyLock::new(|| {
+    BUILTIN_SOURCES
+        .iter()
+        .map(|(id, source)| Alphabet::parse(id, source).unwrap())
+        .collect()
+});
+
+/// Ids of the alphabets used when nothing else is selected.
+pub const DEFAULT_ALPHABETS: [&str; 2] = ["ru", "en"];
+
+/// An ordered set of letters. The value of a letter is derived from its
+/// position, so the order matters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    id: String,
+    name: String,
+    letters: Vec<Vec<char>>,
+}
+
+impl Alphabet {
+    /// Parses an alphabet definition.
+    ///
+    /// Lines starting with `#` are comments, an optional `name = ...` line
+    /// sets the display name, all other tokens are letters in order.
+    pub fn parse(id: &str, source: &str) -> Result<Self> {
+        let mut name = None;
+        let mut letters: Vec<Vec<char>> = Vec::new();
+
+        for line in source.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(value) = line.strip_prefix("name")
+                && let Some(value) = value.trim_start().strip_prefix('=')
+            {
+                name = Some(value.trim().to_string());
+                continue;
+            }
+
+            for token in line.split_whitespace() {
+                let spellings = token.chars().flat_map(char::to_lowercase).collect();
+
+                letters.push(spellings);
+            }
+        }
+
+        if letters.is_empty() {
+            return Err(Error::EmptyAlphabet { id: id.to_string() });
+        }
+
+        Ok(Self {
+            id: id.to_string(),
+            name: name.unwrap_or_else(|| id.to_string()),
+            letters,
+        })
+    }
+
+    /// Loads an alphabet file, using the file stem as its id.
+    pub fn load(path: &Path) -> Result<Self> {
+        let id = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let source = std::fs::read_to_string(path).map_err(|source| Error::ReadAlphabet {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        Self::parse(&id, &source)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    /// Zero based position of a lowercase letter.
+    pub fn position(&self, letter: char) -> Option<usize> {
+        self.letters
+            .iter()
+            .position(|spellings| spellings.contains(&letter))
+    }
+
+    /// Every spelling of every letter together with its position.
+    pub fn letters(&self) -> impl Iterator<Item = (char, usize)> + '_ {
+        self.letters
+            .iter()
+            .enumerate()
+            .flat_map(|(position, spellings)| {
+                spellings.iter().map(move |&letter| (letter, position))
+            })
+    }
+}
+
+pub fn builtin() -> &'static [Alphabet] {
+    &BUILTIN
+}
+
+pub fn find(id: &str) -> Option<&'static Alphabet> {
+    BUILTIN.iter().find(|alphabet| alphabet.id == id)
+}
+
+pub fn default_alphabets() -> Vec<Alphabet> {
+    DEFAULT_ALPHABETS
+        .iter()
+        .filter_map(|id| find(id).cloned())
+        .collect()
+}
+
+/// Loads every `*.txt` file in `dir` as an alphabet. A missing directory is
+/// not an error and yields nothing.
+pub fn load_dir(dir: &Path) -> Vec<Result<Alphabet>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .collect::<Vec<_>>();
+
+    paths.sort();
+
+    paths.iter().map(|path| Alphabet::load(path)).collect()
+}
@@ -1,83 +1,306 @@
 use std::collections::HashMap;
-use std::sync::LazyLock;
 
-// The source string containing UTF-8 symbols
-const CYR_SYMBOL_STRING: &str = "абвгдеёжзийклмнопрстуфхцчшщъыьэюя";
-const LAT_SYMBOL_STRING: &str = "abcdefghijklmnopqrstuvwxyz";
+use crate::alphabet::{Alphabet, default_alphabets};
+use crate::date::BirthDate;
+use crate::error::{Error, Result};
+use crate::grid::MandalaGrid;
+use crate::normalize::{Normalization, Normalized, normalize};
+use crate::numbers::{NumberMode, parse_roman};
+use crate::numerology::{NumerologySystem, digit_value, reduce};
+use crate::trace::{PaddingStep, Trace};
 
-fn index_generator() -> impl Iterator<Item = u16> {
-    (1..=9).cycle()
-}
+/// Seed widths offered by the application. Any width from [`MIN_WIDTH`] to
+/// [`MAX_WIDTH`] is accepted by the calculation.
+pub const SEED_WIDTHS: [usize; 4] = [6, 8, 10, 12];
+pub const DEFAULT_WIDTH: usize = 8;
+pub const MIN_WIDTH: usize = 2;
+/// The triangle grows with the square of the width, and its cells get too
+/// small to print well before this.
+pub const MAX_WIDTH: usize = 64;
 
-// Static immutable map of UTF-8 symbols to u16 values
-static SYMBOL_MAP: LazyLock<HashMap<char, u16>> = LazyLock::new(|| {
-    let cyr_symbols = CYR_SYMBOL_STRING.chars().zip(index_generator());
-    let lat_symbols = LAT_SYMBOL_STRING.chars().zip(index_generator());
+/// Moduli offered by the application: a rainbow, the classic digits and a
+/// zodiac wheel. Any modulus from [`MIN_MODULUS`] to [`MAX_MODULUS`] is
+/// accepted.
+pub const MODULI: [u16; 3] = [7, 9, 12];
+pub const DEFAULT_MODULUS: u16 = 9;
+pub const MIN_MODULUS: u16 = 2;
+/// More digits than this could not be told apart by colour anyway.
+pub const MAX_MODULUS: u16 = 99;
 
-    cyr_symbols.chain(lat_symbols).collect()
-});
+#[derive(Debug, Clone)]
+pub struct CalculationOptions {
+    /// Active alphabets. When a letter belongs to several of them the first
+    /// alphabet in the list decides its value.
+    pub alphabets: Vec<Alphabet>,
+    pub system: NumerologySystem,
+    /// Number of digits in the seed. The mandala has twice as many rows.
+    pub width: usize,
+    /// Digits are reduced into `1..=modulus`.
+    pub modulus: u16,
+    pub numbers: NumberMode,
+    pub normalization: Normalization,
+}
 
-fn get_sum(index_a: u16, index_b: u16) -> u16 {
-    let sum = index_a + index_b;
-    if sum > 9 { sum - 9 } else { sum }
+impl Default for CalculationOptions {
+    fn default() -> Self {
+        Self {
+            alphabets: default_alphabets(),
+            system: NumerologySystem::default(),
+            width: DEFAULT_WIDTH,
+            modulus: DEFAULT_MODULUS,
+            numbers: NumberMode::default(),
+            normalization: Normalization::default(),
+        }
+    }
 }
 
-pub fn calculate_mandala(text: &str) -> Result<Vec<Vec<u16>>, String> {
-    let mut indexes_a = text
-        .chars()
-        .map(|letter| SYMBOL_MAP.get(&letter.to_lowercase().next().unwrap()))
-        .filter(|index| index.is_some())
-        .map(|index| *(index.unwrap()))
-        .collect::<Vec<u16>>();
+/// A piece of the input text together with the value it contributes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Token {
+    /// A single character, or a whole Roman numeral.
+    pub text: String,
+    /// `None` when the piece is skipped by the calculation.
+    pub value: Option<u16>,
+}
 
-    if indexes_a.len() < 2 {
-        return Err("Слишком короткий текст".to_string());
+impl Token {
+    /// Whether the piece is skipped although it is not plain whitespace.
+    pub fn is_ignored(&self) -> bool {
+        self.value.is_none() && !self.text.chars().all(char::is_whitespace)
     }
+}
+
+// Map of lowercase letters to their values for the active alphabets
+fn symbol_map(
+    alphabets: &[Alphabet],
+    system: NumerologySystem,
+    modulus: u16,
+) -> HashMap<char, u16> {
+    let mut symbols = HashMap::new();
 
-    while indexes_a.len() < 8 {
-        let last_index = indexes_a.last().unwrap();
-        let second_last_index = indexes_a.get(indexes_a.len() - 2).unwrap();
-        let sum = get_sum(*last_index, *second_last_index);
-        indexes_a.push(sum);
+    for alphabet in alphabets {
+        for (letter, position) in alphabet.letters() {
+            symbols
+                .entry(letter)
+                .or_insert_with(|| system.value(letter, position, modulus));
+        }
     }
 
-    let mut size = indexes_a.len();
-    let mut iteration: u16 = 0;
-    let mut indexes_b: Vec<u16> = vec![0; size];
+    symbols
+}
+
+// Splits `text` into characters and Roman numerals, valuing the recognised ones
+fn tokenize(
+    text: &str,
+    symbols: &HashMap<char, u16>,
+    numbers: NumberMode,
+    modulus: u16,
+) -> Vec<Token> {
+    let mut tokens = Vec::new();
+
+    let char_token = |symbol: char| {
+        let value = symbols
+            .get(&symbol.to_lowercase().next().unwrap())
+            .copied()
+            .or_else(|| {
+                symbol
+                    .to_digit(10)
+                    .filter(|_| numbers.digits() && symbol.is_ascii())
+                    .map(|digit| digit_value(digit, modulus))
+            });
 
-    while size > 8 {
-        let (from, to) = if iteration % 2 == 0 {
-            (&mut indexes_a, &mut indexes_b)
-        } else {
-            (&mut indexes_b, &mut indexes_a)
+        Token {
+            text: symbol.to_string(),
+            value,
+        }
+    };
+
+    for piece in text.split_inclusive(|symbol: char| !symbol.is_alphanumeric()) {
+        let (word, separator) = match piece.char_indices().last() {
+            Some((index, last)) if !last.is_alphanumeric() => (&piece[..index], Some(last)),
+            _ => (piece, None),
         };
 
-        for i in 1..size {
-            to[i - 1] = get_sum(from[i - 1], from[i]);
+        match parse_roman(word).filter(|_| numbers.roman()) {
+            Some(number) => tokens.push(Token {
+                text: word.to_string(),
+                value: Some(reduce(number, modulus)),
+            }),
+            None => tokens.extend(word.chars().map(char_token)),
         }
 
-        size -= 1;
-        iteration += 1;
+        if let Some(separator) = separator {
+            tokens.push(char_token(separator));
+        }
     }
 
-    let reduced = if iteration % 2 == 0 {
-        &indexes_a
-    } else {
-        &indexes_b
-    };
+    tokens
+}
+
+// Normalises `text` and splits the result into tokens
+fn analyse(text: &str, options: &CalculationOptions) -> (Normalized, Vec<Token>) {
+    let symbols = symbol_map(&options.alphabets, options.system, options.modulus);
+    let normalized = normalize(text, &options.normalization, |symbol| {
+        symbols.contains_key(&symbol.to_lowercase().next().unwrap())
+    });
+    let tokens = tokenize(&normalized.text, &symbols, options.numbers, options.modulus);
+
+    (normalized, tokens)
+}
+
+/// Shows how the calculation reads `text`: every character of the
+/// normalised text, Roman numerals kept whole, with its value or `None` when
+/// it is ignored.
+pub fn analyse_text(text: &str, options: &CalculationOptions) -> Result<Vec<Token>> {
+    check_options(options)?;
+
+    Ok(analyse(text, options).1)
+}
+
+fn get_sum(index_a: u16, index_b: u16, modulus: u16) -> u16 {
+    let sum = index_a + index_b;
+    if sum > modulus { sum - modulus } else { sum }
+}
+
+pub fn calculate_mandala(text: &str, options: &CalculationOptions) -> Result<MandalaGrid> {
+    calculate_mandala_with_trace(text, options).map(|(grid, _)| grid)
+}
+
+/// Same as [`calculate_mandala`], additionally returning every step that led
+/// to the seed line.
+pub fn calculate_mandala_with_trace(
+    text: &str,
+    options: &CalculationOptions,
+) -> Result<(MandalaGrid, Trace)> {
+    check_options(options)?;
+
+    let (normalized, tokens) = analyse(text, options);
+    let letters = tokens
+        .into_iter()
+        .filter_map(|token| token.value.map(|value| (token.text, value)))
+        .collect();
+
+    grow_mandala(letters, options).map(|(grid, mut trace)| {
+        if normalized.text != text {
+            trace.normalized = Some(normalized.text);
+        }
+        trace.substitutions = normalized.substitutions;
+
+        (grid, trace)
+    })
+}
+
+/// Builds a mandala from a date of birth.
+///
+/// The seed starts from the eight digits of `DDMMYYYY`. A zero counts as the
+/// modulus (sums are taken modulo it, so `0` and `9` are the same digit for
+/// the default modulus), other digits are reduced into `1..=modulus`. The
+/// digits are then padded or folded to the seed width like letter values.
+pub fn calculate_date_mandala(
+    date: &BirthDate,
+    options: &CalculationOptions,
+) -> Result<MandalaGrid> {
+    calculate_date_mandala_with_trace(date, options).map(|(grid, _)| grid)
+}
+
+pub fn calculate_date_mandala_with_trace(
+    date: &BirthDate,
+    options: &CalculationOptions,
+) -> Result<(MandalaGrid, Trace)> {
+    check_options(options)?;
+
+    let digits = date
+        .digits()
+        .iter()
+        .map(|&digit| {
+            (
+                digit.to_string(),
+                digit_value(digit as u32, options.modulus),
+            )
+        })
+        .collect();
+
+    grow_mandala(digits, options)
+}
+
+fn check_options(options: &CalculationOptions) -> Result<()> {
+    if !(MIN_WIDTH..=MAX_WIDTH).contains(&options.width) {
+        return Err(Error::InvalidWidth {
+            width: options.width,
+        });
+    }
+
+    if !(MIN_MODULUS..=MAX_MODULUS).contains(&options.modulus) {
+        return Err(Error::InvalidModulus {
+            modulus: options.modulus,
+        });
+    }
+
+    Ok(())
+}
+
+// Pads or folds the values to the seed width and grows the triangle from them
+fn grow_mandala(
+    letters: Vec<(String, u16)>,
+    options: &CalculationOptions,
+) -> Result<(MandalaGrid, Trace)> {
+    let width = options.width;
+    let modulus = options.modulus;
+
+    if letters.is_empty() {
+        return Err(Error::NoLetters);
+    }
+
+    if letters.len() < 2 {
+        return Err(Error::TooShort {
+            letters: letters.len(),
+            required: 2,
+        });
+    }
+
+    let mut indexes = letters
+        .iter()
+        .map(|&(_, value)| value)
+        .collect::<Vec<u16>>();
+    let mut padding = Vec::new();
+
+    while indexes.len() < width {
+        let left = indexes[indexes.len() - 2];
+        let right = indexes[indexes.len() - 1];
+        let sum = get_sum(left, right, modulus);
+
+        padding.push(PaddingStep { left, right, sum });
+        indexes.push(sum);
+    }
+
+    let mut reductions = Vec::new();
+
+    while indexes.len() > width {
+        let reduced = indexes
+            .windows(2)
+            .map(|pair| get_sum(pair[0], pair[1], modulus))
+            .collect::<Vec<u16>>();
+
+        reductions.push(std::mem::replace(&mut indexes, reduced));
+    }
 
-    let line = reduced
+    if !reductions.is_empty() {
+        reductions.push(indexes.clone());
+    }
+
+    let line = indexes
         .iter()
-        .take(8)
-        .chain(reduced.iter().take(8).rev())
-        .map(|&index| index)
+        .chain(indexes.iter().rev())
+        .copied()
         .collect::<Vec<u16>>();
 
-    let mut result: Vec<Vec<u16>> = Vec::with_capacity(16);
+    let size = line.len();
+    let mut result: Vec<Vec<u16>> = Vec::with_capacity(size);
 
     result.push(line);
 
-    for index in 1..16 {
+    for index in 1..size {
         let previous = result.get(index - 1).unwrap();
 
         let mut row: Vec<u16> = Vec::with_capacity(previous.len() - 1);
@@ -85,12 +308,143 @@ pub fn calculate_mandala(text: &str) -> Result<Vec<Vec<u16>>, String> {
         let row_iter = previous
             .iter()
             .zip(previous.iter().skip(1))
-            .map(|(a, b)| get_sum(*a, *b));
+            .map(|(a, b)| get_sum(*a, *b, modulus));
 
         row.extend(row_iter);
 
         result.push(row);
     }
 
-    Ok(result)
+    result.reverse();
+
+    let trace = Trace {
+        letters,
+        padding,
+        reductions,
+        ..Trace::default()
+    };
+
+    Ok((MandalaGrid::new(result, modulus), trace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_width(width: usize) -> CalculationOptions {
+        CalculationOptions {
+            width,
+            ..CalculationOptions::default()
+        }
+    }
+
+    // Seeds and apexes of the original fixed-width calculation
+    #[test]
+    fn matches_the_original_calculation() {
+        let expected = [
+            ("Анна Каренина", [5, 4, 5, 7, 1, 8, 7, 7], 4),
+            ("Мир", [5, 1, 9, 1, 1, 2, 3, 5], 2),
+            ("Александр Сергеевич Пушкин", [1, 6, 4, 2, 9, 3, 8, 9], 5),
+        ];
+
+        for (name, seed, apex) in expected {
+            let grid = calculate_mandala(name, &CalculationOptions::default()).unwrap();
+
+            assert_eq!(grid.seed(), seed, "{name}");
+            assert_eq!(grid.apex(), apex, "{name}");
+            assert_eq!(grid.size(), 16, "{name}");
+        }
+    }
+
+    #[test]
+    fn pads_short_text() {
+        let (grid, trace) =
+            calculate_mandala_with_trace("Ян", &CalculationOptions::default()).unwrap();
+        let steps = trace
+            .padding
+            .iter()
+            .map(|step| (step.left, step.right, step.sum))
+            .collect::<Vec<_>>();
+
+        assert_eq!(trace.letters, [("Я".to_string(), 6), ("н".to_string(), 6)]);
+        assert_eq!(
+            steps,
+            [
+                (6, 6, 3),
+                (6, 3, 9),
+                (3, 9, 3),
+                (9, 3, 3),
+                (3, 3, 6),
+                (3, 6, 9)
+            ]
+        );
+        assert_eq!(grid.seed(), [6, 6, 3, 9, 3, 3, 6, 9]);
+        assert!(trace.reductions.is_empty());
+    }
+
+    #[test]
+    fn folds_long_text() {
+        let (grid, trace) = calculate_mandala_with_trace(
+            "Александр Сергеевич Пушкин",
+            &CalculationOptions::default(),
+        )
+        .unwrap();
+        let letters = trace
+            .letters
+            .iter()
+            .map(|&(_, value)| value)
+            .collect::<Vec<u16>>();
+
+        assert!(trace.padding.is_empty());
+        assert_eq!(trace.reductions.first(), Some(&letters));
+        assert_eq!(
+            trace.reductions.last().map(Vec::as_slice),
+            Some(grid.seed())
+        );
+        assert_eq!(trace.reductions.len(), letters.len() - 8 + 1);
+
+        for pair in trace.reductions.windows(2) {
+            assert_eq!(pair[1].len(), pair[0].len() - 1);
+        }
+    }
+
+    #[test]
+    fn grows_twice_the_width() {
+        for width in [MIN_WIDTH, 6, 12, MAX_WIDTH] {
+            let grid = calculate_mandala("Анна Каренина", &with_width(width)).unwrap();
+
+            assert_eq!(grid.seed().len(), width);
+            assert_eq!(grid.size(), 2 * width);
+        }
+    }
+
+    #[test]
+    fn rejects_widths_out_of_range() {
+        for width in [0, 1, MAX_WIDTH + 1] {
+            assert!(matches!(
+                calculate_mandala("Анна Каренина", &with_width(width)),
+                Err(Error::InvalidWidth { width: rejected }) if rejected == width
+            ));
+        }
+    }
+
+    #[test]
+    fn zeros_count_as_the_modulus_like_in_dates() {
+        let options = CalculationOptions {
+            numbers: NumberMode::Digits,
+            ..CalculationOptions::default()
+        };
+        let tokens = analyse_text("2005", &options).unwrap();
+
+        assert!(tokens.iter().all(|token| !token.is_ignored()));
+        assert_eq!(
+            tokens.iter().map(|token| token.value).collect::<Vec<_>>(),
+            [Some(2), Some(9), Some(9), Some(5)]
+        );
+
+        let date = BirthDate::parse("01.01.2005").unwrap();
+        let text = calculate_mandala("01012005", &options).unwrap();
+
+        assert_eq!(calculate_date_mandala(&date, &options).unwrap(), text);
+    }
 }
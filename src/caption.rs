@@ -0,0 +1,207 @@
+//! The caption block printed with a mandala: the caption itself, shrunk to
+//! fit its lines, and the optional subtitle, date and dedication under it.
+
+use std::fmt;
+
+use ttf_parser::Face;
+
+use crate::error::{Error, Result};
+use crate::text::{TextLine, layout_text};
+
+/// Size the caption is set in by default, in points.
+pub const DEFAULT_FONT_SIZE: f32 = 12.0;
+/// Lines a caption may take by default.
+pub const DEFAULT_MAX_LINES: usize = 2;
+/// A caption is never shrunk below this size, in points.
+pub const MIN_FONT_SIZE: f32 = 6.0;
+
+// Line spacing relative to the font size
+const LINE_HEIGHT: f32 = 1.2;
+// Size of the subtitle, the date and the dedication relative to the caption
+const DETAIL_SCALE: f32 = 0.75;
+// Step the caption shrinks by while it does not fit
+const SHRINK_STEP: f32 = 0.95;
+
+/// Where the caption goes relative to the mandala.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CaptionPosition {
+    Above,
+    #[default]
+    Below,
+    /// No caption, the mandala takes its room.
+    None,
+}
+
+impl CaptionPosition {
+    pub const ALL: [CaptionPosition; 3] = [
+        CaptionPosition::Above,
+        CaptionPosition::Below,
+        CaptionPosition::None,
+    ];
+}
+
+impl fmt::Display for CaptionPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CaptionPosition::Above => "Подпись сверху",
+            CaptionPosition::Below => "Подпись снизу",
+            CaptionPosition::None => "Без подписи",
+        })
+    }
+}
+
+/// How the caption is set and what is printed along with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptionOptions {
+    pub position: CaptionPosition,
+    /// Size of the caption in points, at least [`MIN_FONT_SIZE`].
+    pub font_size: f32,
+    /// Lines the caption may take. A longer one is set smaller until it
+    /// fits, down to [`MIN_FONT_SIZE`].
+    pub max_lines: usize,
+    /// Printed under the caption in a smaller size when not empty, like the
+    /// date and the dedication.
+    pub subtitle: String,
+    pub date: String,
+    pub dedication: String,
+}
+
+impl Default for CaptionOptions {
+    fn default() -> Self {
+        Self {
+            position: CaptionPosition::default(),
+            font_size: DEFAULT_FONT_SIZE,
+            max_lines: DEFAULT_MAX_LINES,
+            subtitle: String::new(),
+            date: String::new(),
+            dedication: String::new(),
+        }
+    }
+}
+
+impl CaptionOptions {
+    /// Fails when the font size is not a number or smaller than
+    /// [`MIN_FONT_SIZE`].
+    pub fn check(&self) -> Result<()> {
+        check_font_size(self.font_size).map(|_| ())
+    }
+
+    /// The subtitle, the date and the dedication that are given.
+    pub(crate) fn details(&self) -> impl Iterator<Item = &str> {
+        [&self.subtitle, &self.date, &self.dedication]
+            .into_iter()
+            .map(|detail| detail.trim())
+            .filter(|detail| !detail.is_empty())
+    }
+
+    /// Everything printed with a mandala captioned `caption`, nothing when
+    /// the caption is turned off.
+    pub(crate) fn texts<'a>(&'a self, caption: &'a str) -> impl Iterator<Item = &'a str> {
+        let shown = self.position != CaptionPosition::None;
+
+        [caption]
+            .into_iter()
+            .chain(self.details())
+            .filter(move |_| shown)
+    }
+}
+
+/// `size` back when it is a usable font size, finite and not smaller than
+/// [`MIN_FONT_SIZE`].
+pub fn check_font_size(size: f32) -> Result<f32> {
+    if size.is_finite() && size >= MIN_FONT_SIZE {
+        Ok(size)
+    } else {
+        Err(Error::InvalidFontSize { size })
+    }
+}
+
+/// A line of the caption block.
+pub(crate) struct CaptionLine {
+    pub text: TextLine,
+    pub font_size: f32,
+    /// Distance from the top of the block down to the baseline.
+    pub baseline: f32,
+}
+
+/// The laid out caption block, sizes in points.
+#[derive(Default)]
+pub(crate) struct CaptionBlock {
+    pub lines: Vec<CaptionLine>,
+    pub height: f32,
+}
+
+impl CaptionBlock {
+    /// `caption` and the details of `options` wrapped to `max_width`, the
+    /// caption shrunk to fit `options.max_lines`.
+    pub fn new(faces: &[Face], caption: &str, options: &CaptionOptions, max_width: f32) -> Self {
+        let mut block = CaptionBlock::default();
+
+        if options.position == CaptionPosition::None {
+            return block;
+        }
+
+        let max_lines = options.max_lines.max(1);
+        let mut font_size = options.font_size;
+        let mut lines = layout_text(faces, caption, font_size, max_width);
+
+        while lines.len() > max_lines && font_size > MIN_FONT_SIZE {
+            font_size = (font_size * SHRINK_STEP).max(MIN_FONT_SIZE);
+            lines = layout_text(faces, caption, font_size, max_width);
+        }
+
+        for line in lines {
+            block.push(line, font_size);
+        }
+
+        let detail_size = (options.font_size * DETAIL_SCALE).max(MIN_FONT_SIZE);
+
+        for detail in options.details() {
+            for line in layout_text(faces, detail, detail_size, max_width) {
+                block.push(line, detail_size);
+            }
+        }
+
+        block
+    }
+
+    fn push(&mut self, text: TextLine, font_size: f32) {
+        self.lines.push(CaptionLine {
+            text,
+            font_size,
+            baseline: self.height + font_size,
+        });
+        self.height += font_size * LINE_HEIGHT;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unusable_font_sizes() {
+        assert!(CaptionOptions::default().check().is_ok());
+
+        let smallest = CaptionOptions {
+            font_size: MIN_FONT_SIZE,
+            ..CaptionOptions::default()
+        };
+
+        assert!(smallest.check().is_ok());
+
+        for font_size in [0.0, -1.0, 4.0, f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let options = CaptionOptions {
+                font_size,
+                ..CaptionOptions::default()
+            };
+
+            assert!(
+                matches!(options.check(), Err(Error::InvalidFontSize { .. })),
+                "{font_size}"
+            );
+        }
+    }
+}
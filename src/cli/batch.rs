@@ -0,0 +1,199 @@
+//! Lists of people for batch generation.
+
+use std::path::Path;
+
+use mandala::pdf::RejectedRow;
+use mandala::{Error, Result};
+
+// A first row holding one of these in its first column is a header
+const HEADERS: [&str; 4] = ["name", "text", "имя", "текст"];
+
+/// A row of the list: the text to calculate and an optional caption to print
+/// instead of it.
+#[derive(Debug, Clone)]
+pub struct Person {
+    pub line: u64,
+    pub name: String,
+    pub caption: Option<String>,
+}
+
+/// Reads a CSV file, or a TSV file when the path ends in `.tsv` or the first
+/// line contains a tab. Blank rows are skipped, unreadable rows and rows
+/// without a name are returned as rejected.
+pub fn read_people(path: &Path) -> Result<(Vec<Person>, Vec<RejectedRow>)> {
+    let data = std::fs::read(path).map_err(|source| Error::ReadInput {
+        path: Some(path.to_owned()),
+        source,
+    })?;
+    let tsv = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("tsv"));
+
+    Ok(parse_people(&data, tsv))
+}
+
+// The rows of `data`, separated by tabs when `tsv` is set or the first line
+// contains a tab
+fn parse_people(data: &[u8], tsv: bool) -> (Vec<Person>, Vec<RejectedRow>) {
+    let first_line = data.split(|&byte| byte == b'\n').next().unwrap_or_default();
+    let is_tsv = tsv || first_line.contains(&b'\t');
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .delimiter(if is_tsv { b'\t' } else { b',' })
+        .from_reader(data);
+
+    // The reader reports blank lines before a record as part of it
+    let line_at = |position: &csv::Position| {
+        let start = data[position.byte() as usize..]
+            .iter()
+            .take_while(|byte| matches!(byte, b'\r' | b'\n'))
+            .count()
+            + position.byte() as usize;
+
+        data[..start].iter().filter(|&&byte| byte == b'\n').count() as u64 + 1
+    };
+
+    let mut people = Vec::new();
+    let mut rejected = Vec::new();
+
+    for (index, record) in reader.records().enumerate() {
+        let record = match record {
+            Ok(record) => record,
+            Err(error) => {
+                rejected.push(RejectedRow {
+                    line: error.position().map_or(index as u64 + 1, line_at),
+                    text: String::new(),
+                    reason: format!("Строка не читается: {}", error),
+                });
+                continue;
+            }
+        };
+
+        let line = record.position().map_or(index as u64 + 1, line_at);
+        let name = record.get(0).unwrap_or_default();
+
+        if index == 0 && HEADERS.contains(&name.to_lowercase().as_str()) {
+            continue;
+        }
+
+        if name.is_empty() {
+            if record.iter().any(|field| !field.is_empty()) {
+                rejected.push(RejectedRow {
+                    line,
+                    text: record.iter().collect::<Vec<&str>>().join(", "),
+                    reason: "Не указано имя".to_string(),
+                });
+            }
+            continue;
+        }
+
+        people.push(Person {
+            line,
+            name: name.to_string(),
+            caption: record
+                .get(1)
+                .filter(|caption| !caption.is_empty())
+                .map(str::to_string),
+        });
+    }
+
+    (people, rejected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(people: &[Person]) -> Vec<(u64, &str, Option<&str>)> {
+        people
+            .iter()
+            .map(|person| (person.line, person.name.as_str(), person.caption.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn skips_the_header_row() {
+        let (people, rejected) = parse_people("Имя,Подпись\nАнна,Аня\nБорис\n".as_bytes(), false);
+
+        assert_eq!(
+            names(&people),
+            [(2, "Анна", Some("Аня")), (3, "Борис", None)]
+        );
+        assert!(rejected.is_empty());
+
+        // Only the first row can be a header
+        let (people, _) = parse_people("Анна\nname\n".as_bytes(), false);
+
+        assert_eq!(names(&people), [(1, "Анна", None), (2, "name", None)]);
+    }
+
+    #[test]
+    fn numbers_lines_across_blank_lines() {
+        let (people, rejected) = parse_people("\nАнна\n\n\nБорис\r\n\r\nВера".as_bytes(), false);
+
+        assert_eq!(
+            names(&people),
+            [(2, "Анна", None), (5, "Борис", None), (7, "Вера", None)]
+        );
+        assert!(rejected.is_empty());
+    }
+
+    #[test]
+    fn rejects_rows_without_a_name() {
+        let (people, rejected) = parse_people("Анна\n , Подпись\n,\nБорис\n".as_bytes(), false);
+
+        assert_eq!(names(&people), [(1, "Анна", None), (4, "Борис", None)]);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].line, 2);
+        assert_eq!(rejected[0].text, ", Подпись");
+        assert_eq!(rejected[0].reason, "Не указано имя");
+    }
+
+    #[test]
+    fn numbers_lines_after_quoted_line_breaks() {
+        let data = "\"Анна\nКаренина\",\"Дорогой\nАнне\"\nБорис\n";
+        let (people, _) = parse_people(data.as_bytes(), false);
+
+        assert_eq!(
+            names(&people),
+            [
+                (1, "Анна\nКаренина", Some("Дорогой\nАнне")),
+                (4, "Борис", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unreadable_rows() {
+        let (people, rejected) = parse_people(b"\xFF\xFE\nAnna\n", false);
+
+        assert_eq!(names(&people), [(2, "Anna", None)]);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].line, 1);
+    }
+
+    #[test]
+    fn detects_tab_separated_files() {
+        let data = "Анна Каренина\tАня, с любовью\nБорис, Годунов\n";
+        let (people, _) = parse_people(data.as_bytes(), false);
+
+        assert_eq!(
+            names(&people),
+            [
+                (1, "Анна Каренина", Some("Аня, с любовью")),
+                (2, "Борис, Годунов", None)
+            ]
+        );
+
+        let (people, _) = parse_people("Анна, Каренина\n".as_bytes(), true);
+
+        assert_eq!(names(&people), [(1, "Анна, Каренина", None)]);
+
+        let (people, _) = parse_people("Анна, Каренина\n".as_bytes(), false);
+
+        assert_eq!(names(&people), [(1, "Анна", Some("Каренина"))]);
+    }
+}
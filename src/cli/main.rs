@@ -0,0 +1,786 @@
+//! Generates mandalas without opening a window.
+//!
+//! ```text
+//! mandala-cli "Анна Каренина" -o anna.pdf
+//! mandala-cli --date 01.02.1990 --format json
+//! mandala-cli "Анна Каренина" -o anna.svg
+//! mandala-cli "Анна Каренина" --pixels 1080 --background ffffff -o anna.jpg
+//! echo "Natalya" | mandala-cli --transliterate icao -o natalya.json
+//! mandala-cli --batch group.csv -o group.pdf
+//! mandala-cli --batch group.tsv --split -o group/
+//! ```
+
+mod batch;
+
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, ValueEnum};
+use mandala::caption::{
+    CaptionOptions, CaptionPosition, DEFAULT_FONT_SIZE, DEFAULT_MAX_LINES, check_font_size,
+};
+use mandala::files::{sanitize_file_name, unique_path};
+use mandala::fonts::{FontSource, Fonts};
+use mandala::pdf::{
+    COLORING_BOOK_LINE_WIDTH, CellColoring, CellMarks, ColoringBook, DEFAULT_MARGIN, Orientation,
+    PageSize, PdfMandala, PdfOptions, RejectedRow, check_line_width, render_mandala_pdf,
+    render_mandalas_pdf,
+};
+use mandala::raster::{RasterFormat, RasterOptions, Resolution, render_mandala_raster};
+use mandala::svg::render_mandala_svg;
+use mandala::{
+    Alphabet, BirthDate, CalculationOptions, Error, MandalaGrid, Normalization, NormalizationForm,
+    NumberMode, NumerologySystem, Result, Rgb, Trace, TransliterationScheme, alphabet,
+    calculate_date_mandala_with_trace, calculate_mandala_with_trace, transliterate,
+};
+use serde::Serialize;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "mandala-cli",
+    version,
+    about = "Generates a mandala from a text or a date"
+)]
+struct Args {
+    /// Text to build the mandala from. Read from --file or standard input
+    /// when omitted.
+    text: Option<String>,
+
+    /// Read the text from a file.
+    #[arg(short, long, conflicts_with_all = ["text", "date"])]
+    file: Option<PathBuf>,
+
+    /// Build the mandala from a date of birth, DD.MM.YYYY or YYYY-MM-DD.
+    #[arg(short, long, conflicts_with = "text")]
+    date: Option<String>,
+
+    /// Build a PDF for every name listed in a CSV or TSV file. The first
+    /// column holds the name, the optional second one a caption to print
+    /// instead of it. Fails when no row gives a mandala.
+    #[arg(short, long, value_name = "PATH", conflicts_with_all = ["text", "file", "date", "format"])]
+    batch: Option<PathBuf>,
+
+    /// With --batch, write one PDF per name into the output directory
+    /// instead of a single document.
+    #[arg(long, requires = "batch")]
+    split: bool,
+
+    /// Output file, standard output when omitted or `-`.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Output format, guessed from the output extension by default.
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    /// How the cells of a PDF are painted.
+    #[arg(long, value_enum, default_value_t = Coloring::Full)]
+    coloring: Coloring,
+
+    /// Print an uncoloured mandala to colour by hand, with a legend of
+    /// suggested colours.
+    #[arg(long)]
+    coloring_book: bool,
+
+    /// What the cells of a colouring book show.
+    #[arg(long, value_enum, default_value_t = Marks::Digits, requires = "coloring_book")]
+    marks: Marks,
+
+    /// Width of the grid lines of a colouring book in points.
+    #[arg(long, value_name = "PT", default_value_t = COLORING_BOOK_LINE_WIDTH, requires = "coloring_book", value_parser = parse_line_width)]
+    line_width: f32,
+
+    /// Paper size of a PDF.
+    #[arg(long, value_enum, default_value_t = Paper::A4, conflicts_with = "page_width")]
+    page: Paper,
+
+    /// Width of a custom PDF page in millimetres.
+    #[arg(long, value_name = "MM", requires = "page_height")]
+    page_width: Option<f32>,
+
+    /// Height of a custom PDF page in millimetres.
+    #[arg(long, value_name = "MM", requires = "page_width")]
+    page_height: Option<f32>,
+
+    /// Turn the PDF pages sideways.
+    #[arg(long)]
+    landscape: bool,
+
+    /// Blank space along the edges of a PDF page in millimetres.
+    #[arg(long, value_name = "MM", default_value_t = DEFAULT_MARGIN)]
+    margin: f32,
+
+    /// Author stored in the PDF document properties.
+    #[arg(long)]
+    author: Option<String>,
+
+    /// Fix the PDF creation date and document identifiers, so the same
+    /// input always gives a byte-identical file.
+    #[arg(long)]
+    reproducible: bool,
+
+    /// Font of the digits: a TTF or OTF file, or the family name of an
+    /// installed font. Roboto Light when omitted.
+    #[arg(long, value_name = "FONT")]
+    digit_font: Option<FontSource>,
+
+    /// Font of the caption, given the same way as --digit-font.
+    #[arg(long, value_name = "FONT")]
+    caption_font: Option<FontSource>,
+
+    /// Font for the caption characters its font lacks, given the same way
+    /// as --digit-font. Can be repeated, the fonts are tried in order
+    /// before Roboto and the installed fonts.
+    #[arg(long, value_name = "FONT")]
+    fallback_font: Vec<FontSource>,
+
+    /// Where the caption goes relative to the mandala.
+    #[arg(long, value_enum, default_value_t = Placement::Below)]
+    caption_position: Placement,
+
+    /// Size of the caption in points, 6 or more.
+    #[arg(long, value_name = "PT", default_value_t = DEFAULT_FONT_SIZE, value_parser = parse_font_size)]
+    caption_size: f32,
+
+    /// Lines the caption may take before it is set smaller.
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_LINES)]
+    caption_lines: usize,
+
+    /// Line printed under the caption in a smaller size.
+    #[arg(long, value_name = "TEXT")]
+    subtitle: Option<String>,
+
+    /// Date printed under the caption, as is.
+    #[arg(long, value_name = "TEXT")]
+    caption_date: Option<String>,
+
+    /// Dedication printed under the caption.
+    #[arg(long, value_name = "TEXT")]
+    dedication: Option<String>,
+
+    /// Width of a PNG, JPEG or WebP image in pixels.
+    #[arg(long, value_name = "PX", conflicts_with = "dpi")]
+    pixels: Option<u32>,
+
+    /// Resolution of a PNG, JPEG or WebP image printed as wide as an A4
+    /// page.
+    #[arg(long)]
+    dpi: Option<f32>,
+
+    /// Background of a PNG, JPEG or WebP image as RRGGBB. Transparent when
+    /// omitted, white for JPEG.
+    #[arg(long, value_name = "COLOR", value_parser = parse_color)]
+    background: Option<Rgb>,
+
+    /// Built-in alphabets to use, in order of priority.
+    #[arg(short, long = "alphabet", value_name = "ID", value_delimiter = ',')]
+    alphabets: Vec<String>,
+
+    /// Additional alphabet definition files.
+    #[arg(long = "alphabet-file", value_name = "PATH")]
+    alphabet_files: Vec<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = System::Pythagorean)]
+    system: System,
+
+    /// Number of digits in the seed, 2 to 64.
+    #[arg(long, default_value_t = mandala::calculation::DEFAULT_WIDTH)]
+    width: usize,
+
+    /// Digits are reduced into 1..=MODULUS, MODULUS from 2 to 99.
+    #[arg(long, default_value_t = mandala::calculation::DEFAULT_MODULUS)]
+    modulus: u16,
+
+    /// How numbers in the text contribute to the seed.
+    #[arg(long, value_enum, default_value_t = Numbers::Ignore)]
+    numbers: Numbers,
+
+    #[arg(long, value_enum, default_value_t = Form::Nfc)]
+    normalization: Form,
+
+    /// Read `ё` as `е`.
+    #[arg(long)]
+    yo_as_ye: bool,
+
+    /// Reduce unknown letters with diacritics to their base letters.
+    #[arg(long)]
+    strip_diacritics: bool,
+
+    /// Read `ß` as `ss`.
+    #[arg(long)]
+    fold_sharp_s: bool,
+
+    /// Convert the text into the other script before calculating.
+    #[arg(long, value_enum, value_name = "SCHEME")]
+    transliterate: Option<Scheme>,
+
+    /// Include the calculation steps: extra pages in a PDF, a `trace`
+    /// object in JSON.
+    #[arg(long)]
+    trace: bool,
+
+    /// Make every PDF mandala the cover of a report, followed by its
+    /// triangle, the calculation steps and the digit statistics.
+    #[arg(long)]
+    report: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Pdf,
+    Svg,
+    Png,
+    Jpeg,
+    Webp,
+    Json,
+}
+
+impl Format {
+    fn raster(&self) -> Option<RasterFormat> {
+        match self {
+            Format::Png => Some(RasterFormat::Png),
+            Format::Jpeg => Some(RasterFormat::Jpeg),
+            Format::Webp => Some(RasterFormat::Webp),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum System {
+    Pythagorean,
+    Chaldean,
+    Gematria,
+    Church,
+}
+
+impl From<System> for NumerologySystem {
+    fn from(system: System) -> Self {
+        match system {
+            System::Pythagorean => NumerologySystem::Pythagorean,
+            System::Chaldean => NumerologySystem::Chaldean,
+            System::Gematria => NumerologySystem::Gematria,
+            System::Church => NumerologySystem::Church,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Numbers {
+    Ignore,
+    Digits,
+    Roman,
+}
+
+impl From<Numbers> for NumberMode {
+    fn from(numbers: Numbers) -> Self {
+        match numbers {
+            Numbers::Ignore => NumberMode::Ignore,
+            Numbers::Digits => NumberMode::Digits,
+            Numbers::Roman => NumberMode::DigitsAndRoman,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Form {
+    None,
+    Nfc,
+    Nfkc,
+}
+
+impl From<Form> for NormalizationForm {
+    fn from(form: Form) -> Self {
+        match form {
+            Form::None => NormalizationForm::None,
+            Form::Nfc => NormalizationForm::Nfc,
+            Form::Nfkc => NormalizationForm::Nfkc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Scheme {
+    Iso9,
+    GostB,
+    Icao,
+    Passport1997,
+}
+
+impl From<Scheme> for TransliterationScheme {
+    fn from(scheme: Scheme) -> Self {
+        match scheme {
+            Scheme::Iso9 => TransliterationScheme::Iso9,
+            Scheme::GostB => TransliterationScheme::GostB,
+            Scheme::Icao => TransliterationScheme::Icao,
+            Scheme::Passport1997 => TransliterationScheme::Passport1997,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Coloring {
+    Full,
+    Tinted,
+    Plain,
+}
+
+impl From<Coloring> for CellColoring {
+    fn from(coloring: Coloring) -> Self {
+        match coloring {
+            Coloring::Full => CellColoring::Full,
+            Coloring::Tinted => CellColoring::Tinted,
+            Coloring::Plain => CellColoring::Plain,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Marks {
+    Digits,
+    Dots,
+    None,
+}
+
+impl From<Marks> for CellMarks {
+    fn from(marks: Marks) -> Self {
+        match marks {
+            Marks::Digits => CellMarks::Digits,
+            Marks::Dots => CellMarks::Dots,
+            Marks::None => CellMarks::Blank,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Paper {
+    A3,
+    A4,
+    A5,
+    Letter,
+    Square,
+}
+
+impl From<Paper> for PageSize {
+    fn from(paper: Paper) -> Self {
+        match paper {
+            Paper::A3 => PageSize::A3,
+            Paper::A4 => PageSize::A4,
+            Paper::A5 => PageSize::A5,
+            Paper::Letter => PageSize::Letter,
+            Paper::Square => PageSize::Square,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Placement {
+    Above,
+    Below,
+    None,
+}
+
+impl From<Placement> for CaptionPosition {
+    fn from(placement: Placement) -> Self {
+        match placement {
+            Placement::Above => CaptionPosition::Above,
+            Placement::Below => CaptionPosition::Below,
+            Placement::None => CaptionPosition::None,
+        }
+    }
+}
+
+// What the JSON output contains
+#[derive(Serialize)]
+struct Report<'a> {
+    input: &'a str,
+    system: Option<NumerologySystem>,
+    width: usize,
+    modulus: u16,
+    seed: &'a [u16],
+    line: &'a [u16],
+    apex: u16,
+    /// Apex first, down to the line.
+    rows: Vec<&'a [u16]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace: Option<&'a Trace>,
+}
+
+// A calculated row of a batch
+struct BatchMandala {
+    text: String,
+    caption: Option<String>,
+    grid: MandalaGrid,
+    trace: Trace,
+}
+
+impl BatchMandala {
+    fn caption(&self) -> &str {
+        self.caption.as_deref().unwrap_or(&self.text)
+    }
+
+    fn pdf(&self, system: NumerologySystem, with_trace: bool) -> PdfMandala<'_> {
+        PdfMandala {
+            grid: &self.grid,
+            input: &self.text,
+            caption: self.caption(),
+            system: Some(system),
+            trace: Some(&self.trace).filter(|_| with_trace),
+        }
+    }
+}
+
+impl Args {
+    fn calculation_options(&self) -> Result<CalculationOptions> {
+        let mut alphabets = if self.alphabets.is_empty() && self.alphabet_files.is_empty() {
+            alphabet::default_alphabets()
+        } else {
+            self.alphabets
+                .iter()
+                .map(|id| {
+                    alphabet::find(id)
+                        .cloned()
+                        .ok_or_else(|| Error::UnknownAlphabet { id: id.to_owned() })
+                })
+                .collect::<Result<Vec<Alphabet>>>()?
+        };
+
+        for path in &self.alphabet_files {
+            alphabets.push(Alphabet::load(path)?);
+        }
+
+        Ok(CalculationOptions {
+            alphabets,
+            system: self.system.into(),
+            width: self.width,
+            modulus: self.modulus,
+            numbers: self.numbers.into(),
+            normalization: Normalization {
+                form: self.normalization.into(),
+                yo_as_ye: self.yo_as_ye,
+                strip_diacritics: self.strip_diacritics,
+                fold_sharp_s: self.fold_sharp_s,
+            },
+        })
+    }
+
+    fn read_text(&self) -> Result<String> {
+        if let Some(text) = &self.text {
+            return Ok(text.to_owned());
+        }
+
+        let mut text = String::new();
+
+        match &self.file {
+            Some(path) => {
+                text = std::fs::read_to_string(path).map_err(|source| Error::ReadInput {
+                    path: Some(path.to_owned()),
+                    source,
+                })?;
+            }
+            None => {
+                io::stdin()
+                    .read_to_string(&mut text)
+                    .map_err(|source| Error::ReadInput { path: None, source })?;
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    // Standard output when no path or `-` is given
+    fn output_path(&self) -> Option<&Path> {
+        self.output
+            .as_deref()
+            .filter(|path| *path != Path::new("-"))
+    }
+
+    fn pdf_options(&self) -> PdfOptions {
+        let page_size = match (self.page_width, self.page_height) {
+            (Some(width), Some(height)) => PageSize::Custom { width, height },
+            _ => self.page.into(),
+        };
+
+        PdfOptions {
+            coloring: self.coloring.into(),
+            coloring_book: self.coloring_book.then(|| ColoringBook {
+                marks: self.marks.into(),
+                line_width: self.line_width,
+            }),
+            page_size,
+            orientation: if self.landscape {
+                Orientation::Landscape
+            } else {
+                Orientation::Portrait
+            },
+            margin: self.margin,
+            fonts: self.fonts(),
+            caption: self.caption_options(),
+            author: self.author.clone().unwrap_or_default(),
+            reproducible: self.reproducible,
+            report: self.report,
+        }
+    }
+
+    fn raster_options(&self, format: RasterFormat) -> RasterOptions {
+        let resolution = match (self.pixels, self.dpi) {
+            (Some(pixels), _) => Resolution::Width(pixels),
+            (None, Some(dpi)) => Resolution::Dpi(dpi),
+            (None, None) => Resolution::default(),
+        };
+
+        RasterOptions {
+            format,
+            resolution,
+            background: self.background,
+            fonts: self.fonts(),
+            caption: self.caption_options(),
+        }
+    }
+
+    fn fonts(&self) -> Fonts {
+        Fonts {
+            digits: self.digit_font.clone().unwrap_or_default(),
+            caption: self.caption_font.clone().unwrap_or_default(),
+            fallback: self.fallback_font.clone(),
+        }
+    }
+
+    fn caption_options(&self) -> CaptionOptions {
+        CaptionOptions {
+            position: self.caption_position.into(),
+            font_size: self.caption_size,
+            max_lines: self.caption_lines,
+            subtitle: self.subtitle.clone().unwrap_or_default(),
+            date: self.caption_date.clone().unwrap_or_default(),
+            dedication: self.dedication.clone().unwrap_or_default(),
+        }
+    }
+
+    fn format(&self) -> Format {
+        self.format.unwrap_or_else(|| {
+            match self
+                .output_path()
+                .and_then(Path::extension)
+                .and_then(|extension| extension.to_str())
+            {
+                Some(extension) if extension.eq_ignore_ascii_case("pdf") => Format::Pdf,
+                Some(extension) if extension.eq_ignore_ascii_case("svg") => Format::Svg,
+                Some(extension) => match RasterFormat::from_extension(extension) {
+                    Some(RasterFormat::Png) => Format::Png,
+                    Some(RasterFormat::Jpeg) => Format::Jpeg,
+                    Some(RasterFormat::Webp) => Format::Webp,
+                    None => Format::Json,
+                },
+                _ => Format::Json,
+            }
+        })
+    }
+}
+
+fn run(args: &Args) -> Result<()> {
+    let options = args.calculation_options()?;
+
+    if let Some(path) = &args.batch {
+        return run_batch(args, path, &options);
+    }
+
+    let (input, system, (grid, trace)) = match &args.date {
+        Some(date) => {
+            let date = BirthDate::parse(date)?;
+
+            (
+                date.to_string(),
+                None,
+                calculate_date_mandala_with_trace(&date, &options)?,
+            )
+        }
+        None => {
+            let mut text = args.read_text()?;
+
+            if let Some(scheme) = args.transliterate {
+                text = transliterate(&text, scheme.into());
+            }
+
+            let calculation = calculate_mandala_with_trace(&text, &options)?;
+
+            (text, Some(options.system), calculation)
+        }
+    };
+
+    let format = args.format();
+    // A report always shows how the mandala was calculated
+    let trace = Some(&trace).filter(|_| args.trace || (args.report && format == Format::Pdf));
+    let bytes = match format.raster() {
+        Some(raster) => render_mandala_raster(&grid, &input, &args.raster_options(raster))?,
+        None if format == Format::Pdf => {
+            render_mandala_pdf(&grid, &input, system, trace, &args.pdf_options())?
+        }
+        None if format == Format::Svg => {
+            render_mandala_svg(&grid, &input, &args.fonts(), &args.caption_options())?.into_bytes()
+        }
+        None => render_json(&grid, &input, system, trace),
+    };
+
+    write_output(args.output_path(), &bytes)
+}
+
+fn run_batch(args: &Args, path: &Path, options: &CalculationOptions) -> Result<()> {
+    let (people, mut rejected) = batch::read_people(path)?;
+
+    if people.is_empty() && rejected.is_empty() {
+        return Err(Error::EmptyBatch {
+            path: path.to_owned(),
+        });
+    }
+
+    let mut mandalas = Vec::new();
+
+    for person in people {
+        let text = match args.transliterate {
+            Some(scheme) => transliterate(&person.name, scheme.into()),
+            None => person.name,
+        };
+
+        match calculate_mandala_with_trace(&text, options) {
+            Ok((grid, trace)) => mandalas.push(BatchMandala {
+                text,
+                caption: person.caption,
+                grid,
+                trace,
+            }),
+            Err(error) => rejected.push(RejectedRow {
+                line: person.line,
+                text,
+                reason: error.describe(),
+            }),
+        }
+    }
+
+    rejected.sort_by_key(|row| row.line);
+
+    for row in &rejected {
+        eprintln!(
+            "mandala-cli: skipped line {} \"{}\": {}",
+            row.line, row.text, row.reason
+        );
+    }
+
+    if mandalas.is_empty() {
+        return Err(Error::NoMandalas {
+            path: path.to_owned(),
+            rejected: rejected.len(),
+        });
+    }
+
+    if !args.split {
+        let mandalas = mandalas
+            .iter()
+            .map(|mandala| mandala.pdf(options.system, args.trace || args.report))
+            .collect::<Vec<_>>();
+
+        return write_output(
+            args.output_path(),
+            &render_mandalas_pdf(&mandalas, &rejected, &args.pdf_options())?,
+        );
+    }
+
+    let directory = args.output_path().unwrap_or(Path::new("."));
+
+    std::fs::create_dir_all(directory).map_err(|source| Error::Write {
+        path: directory.to_owned(),
+        source,
+    })?;
+
+    for mandala in &mandalas {
+        let stem = sanitize_file_name(&format!("Мандала {}", mandala.caption()), "Мандала");
+        let path = unique_path(directory, &stem, "pdf");
+
+        write_output(
+            Some(&path),
+            &render_mandalas_pdf(
+                &[mandala.pdf(options.system, args.trace || args.report)],
+                &[],
+                &args.pdf_options(),
+            )?,
+        )?;
+    }
+
+    Ok(())
+}
+
+// Standard output when `path` is `None`
+fn write_output(path: Option<&Path>, bytes: &[u8]) -> Result<()> {
+    match path {
+        Some(path) => std::fs::write(path, bytes).map_err(|source| Error::Write {
+            path: path.to_owned(),
+            source,
+        }),
+        None => io::stdout()
+            .write_all(bytes)
+            .map_err(|source| Error::Write {
+                path: PathBuf::from("-"),
+                source,
+            }),
+    }
+}
+
+// `RRGGBB`, optionally prefixed with `#`
+fn parse_color(value: &str) -> std::result::Result<Rgb, String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+
+    match u32::from_str_radix(hex, 16) {
+        Ok(color) if hex.len() == 6 => Ok(Rgb::from_hex(color)),
+        _ => Err(format!("expected a colour as RRGGBB, got \"{}\"", value)),
+    }
+}
+
+// A positive number of points
+fn parse_font_size(value: &str) -> std::result::Result<f32, String> {
+    let size = value.parse::<f32>().map_err(|error| error.to_string())?;
+
+    check_font_size(size).map_err(|error| error.to_string())
+}
+
+// A positive number of points
+fn parse_line_width(value: &str) -> std::result::Result<f32, String> {
+    let width = value.parse::<f32>().map_err(|error| error.to_string())?;
+
+    check_line_width(width).map_err(|error| error.to_string())
+}
+
+fn render_json(
+    grid: &MandalaGrid,
+    input: &str,
+    system: Option<NumerologySystem>,
+    trace: Option<&Trace>,
+) -> Vec<u8> {
+    let report = Report {
+        input,
+        system,
+        width: grid.seed().len(),
+        modulus: grid.modulus(),
+        seed: grid.seed(),
+        line: grid.line(),
+        apex: grid.apex(),
+        rows: grid.rows().collect(),
+        trace,
+    };
+
+    let mut json = serde_json::to_vec_pretty(&report).expect("report contains only plain data");
+    json.push(b'\n');
+    json
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("mandala-cli: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// Latest year a date may have, the last one written with four digits.
+pub const MAX_YEAR: u16 = 9999;
+
+/// A validated calendar date, accepted as `DD.MM.YYYY` or ISO `YYYY-MM-DD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BirthDate {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl BirthDate {
+    /// Fails with [`Error::InvalidDate`] when the date does not exist or its
+    /// year is not within `1..=MAX_YEAR`.
+    pub fn new(year: u16, month: u8, day: u8) -> Result<Self> {
+        if !(1..=MAX_YEAR).contains(&year)
+            || !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+        {
+            return Err(Error::InvalidDate { year, month, day });
+        }
+
+        Ok(Self { year, month, day })
+    }
+
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let invalid_format = || Error::InvalidDateFormat {
+            input: input.to_string(),
+        };
+
+        let parts = if input.contains('.') {
+            let parts =
+                split_numbers(input, '.', [1..=2, 1..=2, 4..=4]).ok_or_else(invalid_format)?;
+
+            [parts[2], parts[1], parts[0]]
+        } else {
+            split_numbers(input, '-', [4..=4, 1..=2, 1..=2]).ok_or_else(invalid_format)?
+        };
+
+        let [year, month, day] = parts;
+
+        Self::new(
+            year as u16,
+            u8::try_from(month).map_err(|_| invalid_format())?,
+            u8::try_from(day).map_err(|_| invalid_format())?,
+        )
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The eight digits of `DDMMYYYY`.
+    pub fn digits(&self) -> [u16; 8] {
+        let (day, month, year) = (self.day as u16, self.month as u16, self.year);
+
+        [
+            day / 10,
+            day % 10,
+            month / 10,
+            month % 10,
+            year / 1000,
+            year / 100 % 10,
+            year / 10 % 10,
+            year % 10,
+        ]
+    }
+}
+
+impl FromStr for BirthDate {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        Self::parse(input)
+    }
+}
+
+impl fmt::Display for BirthDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}.{:02}.{:04}", self.day, self.month, self.year)
+    }
+}
+
+// Splits `input` into three numbers, each with an allowed number of digits
+fn split_numbers(
+    input: &str,
+    separator: char,
+    lengths: [RangeInclusive<usize>; 3],
+) -> Option<[u32; 3]> {
+    let mut parts = input.split(separator);
+    let mut numbers = [0; 3];
+
+    for (number, length) in numbers.iter_mut().zip(lengths) {
+        let part = parts.next()?;
+
+        if !length.contains(&part.len()) || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        *number = part.parse().ok()?;
+    }
+
+    parts.next().is_none().then_some(numbers)
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_formats() {
+        let date = BirthDate::new(1990, 3, 7).unwrap();
+
+        assert_eq!(BirthDate::parse("07.03.1990").unwrap(), date);
+        assert_eq!(BirthDate::parse("7.3.1990").unwrap(), date);
+        assert_eq!(BirthDate::parse(" 1990-03-07 ").unwrap(), date);
+        assert_eq!(BirthDate::parse("1990-3-7").unwrap(), date);
+        assert_eq!(date.to_string(), "07.03.1990");
+        assert_eq!(date.digits(), [0, 7, 0, 3, 1, 9, 9, 0]);
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        for input in [
+            "",
+            "07/03/1990",
+            "07.03.90",
+            "1990.03.07",
+            "07-03-1990",
+            "1990-03-07-01",
+            "07.03.1990.",
+            "+7.03.1990",
+        ] {
+            assert!(
+                matches!(
+                    BirthDate::parse(input),
+                    Err(Error::InvalidDateFormat { .. })
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn knows_leap_years() {
+        assert!(BirthDate::parse("29.02.2000").is_ok());
+        assert!(BirthDate::parse("29.02.2024").is_ok());
+        assert!(BirthDate::parse("2024-02-29").is_ok());
+
+        for input in [
+            "29.02.1900",
+            "29.02.2023",
+            "2100-02-29",
+            "30.02.2000",
+            "31.04.2000",
+        ] {
+            assert!(
+                matches!(BirthDate::parse(input), Err(Error::InvalidDate { .. })),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_years_to_four_digits() {
+        assert!(BirthDate::new(1, 1, 1).is_ok());
+        assert!(BirthDate::new(MAX_YEAR, 12, 31).is_ok());
+        assert!(matches!(
+            BirthDate::new(0, 1, 1),
+            Err(Error::InvalidDate { .. })
+        ));
+        assert!(matches!(
+            BirthDate::new(MAX_YEAR + 1, 1, 1),
+            Err(Error::InvalidDate { .. })
+        ));
+        assert!(matches!(
+            BirthDate::parse("0000-01-01"),
+            Err(Error::InvalidDate { .. })
+        ));
+    }
+}
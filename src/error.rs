@@ -0,0 +1,252 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::calculation::{MIN_MODULUS, MIN_WIDTH};
+use crate::caption::MIN_FONT_SIZE;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Fewer letters were recognised than a mandala needs.
+    TooShort { letters: usize, required: usize },
+    /// None of the characters belong to the active alphabets.
+    NoLetters,
+    /// The requested seed width is too small to build a mandala, or larger
+    /// than [`MAX_WIDTH`](crate::calculation::MAX_WIDTH).
+    InvalidWidth { width: usize },
+    /// The requested modulus leaves no room for distinct digits, or is
+    /// larger than [`MAX_MODULUS`](crate::calculation::MAX_MODULUS).
+    InvalidModulus { modulus: u16 },
+    /// A date is not written as `DD.MM.YYYY` or `YYYY-MM-DD`.
+    InvalidDateFormat { input: String },
+    /// A date does not exist in the calendar.
+    InvalidDate { year: u16, month: u8, day: u8 },
+    /// An alphabet definition contains no letters.
+    EmptyAlphabet { id: String },
+    /// No alphabet with this id is built in.
+    UnknownAlphabet { id: String },
+    /// An alphabet file could not be read.
+    ReadAlphabet { path: PathBuf, source: io::Error },
+    /// The input text could not be read from a file, or from standard input
+    /// when `path` is `None`.
+    ReadInput {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+    /// A batch file lists nobody to build a mandala for.
+    EmptyBatch { path: PathBuf },
+    /// Every row of a batch file was rejected.
+    NoMandalas { path: PathBuf, rejected: usize },
+    /// A font could not be parsed.
+    FontParse,
+    /// A font file could not be read.
+    ReadFont { path: PathBuf, source: io::Error },
+    /// No font of this family is installed.
+    FontNotFound { family: String },
+    /// The font chosen for some text cannot draw one of its characters.
+    MissingGlyph { font: String, character: char },
+    /// Neither the chosen fonts nor the installed ones can draw a character.
+    UnsupportedCharacter { character: char },
+    /// A font size is not a number of points or is smaller than
+    /// [`MIN_FONT_SIZE`].
+    InvalidFontSize { size: f32 },
+    /// A line width is not a positive number of points.
+    InvalidLineWidth { width: f32 },
+    /// The page leaves no room for the mandala within its margins, sizes in
+    /// millimetres.
+    InvalidPage {
+        width: f32,
+        height: f32,
+        margin: f32,
+    },
+    /// The requested image is empty or larger than the renderer allows.
+    ImageSize { width: u32, height: u32 },
+    /// The drawing could not be rasterised or encoded.
+    Rasterize,
+    /// The exported file could not be written.
+    Write { path: PathBuf, source: io::Error },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Message in Russian for the users of the application and of the
+    /// generated documents.
+    pub fn describe(&self) -> String {
+        match self {
+            Error::TooShort { letters, required } => format!(
+                "Слишком короткий текст: распознано букв — {}, нужно не меньше {}",
+                letters, required
+            ),
+            Error::NoLetters => "В тексте нет ни одной знакомой буквы".to_string(),
+            Error::InvalidWidth { width } if *width < MIN_WIDTH => {
+                format!("Слишком короткая строка: {} цифр", width)
+            }
+            Error::InvalidWidth { width } => format!("Слишком длинная строка: {} цифр", width),
+            Error::InvalidModulus { modulus } if *modulus < MIN_MODULUS => {
+                format!("Слишком маленькое основание: {}", modulus)
+            }
+            Error::InvalidModulus { modulus } => {
+                format!("Слишком большое основание: {}", modulus)
+            }
+            Error::InvalidDateFormat { input } => format!(
+                "«{}» не похоже на дату, введите ДД.ММ.ГГГГ или ГГГГ-ММ-ДД",
+                input
+            ),
+            Error::InvalidDate { year, month, day } => {
+                format!("Даты {:02}.{:02}.{:04} не существует", day, month, year)
+            }
+            Error::EmptyAlphabet { id } => format!("В алфавите «{}» нет букв", id),
+            Error::UnknownAlphabet { id } => format!("Неизвестный алфавит «{}»", id),
+            Error::ReadAlphabet { path, source } => {
+                format!(
+                    "Не удалось прочитать алфавит {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            Error::ReadInput { path, source } => match path {
+                Some(path) => format!("Не удалось прочитать {}: {}", path.display(), source),
+                None => format!("Не удалось прочитать текст: {}", source),
+            },
+            Error::EmptyBatch { path } => format!("В файле {} нет ни одного имени", path.display()),
+            Error::NoMandalas { path, rejected } => format!(
+                "Ни для одной строки файла {} не удалось построить мандалу, пропущено строк — {}",
+                path.display(),
+                rejected
+            ),
+            Error::FontParse => "Не удалось загрузить шрифт".to_string(),
+            Error::ReadFont { path, source } => {
+                format!("Не удалось прочитать шрифт {}: {}", path.display(), source)
+            }
+            Error::FontNotFound { family } => format!("Шрифт «{}» не установлен", family),
+            Error::MissingGlyph { font, character } => format!(
+                "В шрифте «{}» нет символа «{}» (U+{:04X})",
+                font, character, *character as u32
+            ),
+            Error::UnsupportedCharacter { character } => format!(
+                "Ни в одном шрифте нет символа «{}» (U+{:04X})",
+                character, *character as u32
+            ),
+            Error::InvalidFontSize { size } => {
+                format!(
+                    "Размер шрифта должен быть не меньше {} pt, а не {}",
+                    MIN_FONT_SIZE, size
+                )
+            }
+            Error::InvalidLineWidth { width } => {
+                format!("Толщина линий должна быть больше нуля, а не {}", width)
+            }
+            Error::InvalidPage {
+                width,
+                height,
+                margin,
+            } => format!(
+                "На странице {}×{} мм с полями {} мм не помещается мандала",
+                width, height, margin
+            ),
+            Error::ImageSize { width, height } => format!(
+                "Нельзя создать изображение размером {}×{} пикселей",
+                width, height
+            ),
+            Error::Rasterize => "Не удалось создать изображение".to_string(),
+            Error::Write { path, source } => {
+                format!("Не удалось сохранить {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::TooShort { letters, required } => write!(
+                f,
+                "text is too short: {} letter(s) recognised, at least {} required",
+                letters, required
+            ),
+            Error::NoLetters => write!(f, "text contains no recognised letters"),
+            Error::InvalidWidth { width } if *width < MIN_WIDTH => {
+                write!(f, "seed width {} is too small", width)
+            }
+            Error::InvalidWidth { width } => write!(f, "seed width {} is too large", width),
+            Error::InvalidModulus { modulus } if *modulus < MIN_MODULUS => {
+                write!(f, "modulus {} is too small", modulus)
+            }
+            Error::InvalidModulus { modulus } => write!(f, "modulus {} is too large", modulus),
+            Error::InvalidDateFormat { input } => write!(
+                f,
+                "\"{}\" is not a date, expected DD.MM.YYYY or YYYY-MM-DD",
+                input
+            ),
+            Error::InvalidDate { year, month, day } => {
+                write!(f, "{:02}.{:02}.{:04} does not exist", day, month, year)
+            }
+            Error::EmptyAlphabet { id } => write!(f, "alphabet \"{}\" has no letters", id),
+            Error::UnknownAlphabet { id } => write!(f, "unknown alphabet \"{}\"", id),
+            Error::ReadAlphabet { path, .. } => {
+                write!(f, "failed to read alphabet {}", path.display())
+            }
+            Error::ReadInput { path, .. } => match path {
+                Some(path) => write!(f, "failed to read {}", path.display()),
+                None => write!(f, "failed to read standard input"),
+            },
+            Error::EmptyBatch { path } => write!(f, "{} lists no names", path.display()),
+            Error::NoMandalas { path, rejected } => write!(
+                f,
+                "no mandala could be built from {}, all {} row(s) were skipped",
+                path.display(),
+                rejected
+            ),
+            Error::FontParse => write!(f, "failed to parse font"),
+            Error::ReadFont { path, .. } => write!(f, "failed to read font {}", path.display()),
+            Error::FontNotFound { family } => write!(f, "font \"{}\" is not installed", family),
+            Error::MissingGlyph { font, character } => write!(
+                f,
+                "font \"{}\" has no glyph for '{}' (U+{:04X})",
+                font, character, *character as u32
+            ),
+            Error::UnsupportedCharacter { character } => write!(
+                f,
+                "no font can draw '{}' (U+{:04X})",
+                character, *character as u32
+            ),
+            Error::InvalidFontSize { size } => {
+                write!(
+                    f,
+                    "font size {} is not a number of points from {} up",
+                    size, MIN_FONT_SIZE
+                )
+            }
+            Error::InvalidLineWidth { width } => {
+                write!(f, "line width {} is not a positive number of points", width)
+            }
+            Error::InvalidPage {
+                width,
+                height,
+                margin,
+            } => write!(
+                f,
+                "a {}x{} mm page with {} mm margins leaves no room for the mandala",
+                width, height, margin
+            ),
+            Error::ImageSize { width, height } => {
+                write!(f, "cannot render a {}x{} pixel image", width, height)
+            }
+            Error::Rasterize => write!(f, "failed to render image"),
+            Error::Write { path, .. } => write!(f, "failed to write {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::ReadAlphabet { source, .. }
+            | Error::ReadInput { source, .. }
+            | Error::ReadFont { source, .. }
+            | Error::Write { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
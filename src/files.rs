@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+/// Longest file name stem produced by [`sanitize_file_name`], in characters.
+pub const MAX_STEM_LENGTH: usize = 100;
+
+// Device names Windows refuses as file names, with any extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Turns `name` into a file name stem valid on every platform: characters
+/// reserved by Windows or unix and control characters become `_`, runs of
+/// whitespace collapse, trailing dots and spaces are dropped and the result
+/// is cut to [`MAX_STEM_LENGTH`] characters. Returns `fallback` when nothing
+/// is left.
+pub fn sanitize_file_name(name: &str, fallback: &str) -> String {
+    let replaced = name
+        .chars()
+        .map(|symbol| match symbol {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            _ if symbol.is_control() && !symbol.is_whitespace() => '_',
+            _ => symbol,
+        })
+        .collect::<String>();
+
+    let stem = replaced
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .chars()
+        .take(MAX_STEM_LENGTH)
+        .collect::<String>();
+    let stem = stem.trim_end_matches(['.', ' ']).trim_start_matches('.');
+
+    let reserved = RESERVED_NAMES.iter().any(|reserved| {
+        stem.split('.')
+            .next()
+            .is_some_and(|base| base.trim_end().eq_ignore_ascii_case(reserved))
+    });
+
+    match stem {
+        "" => fallback.to_string(),
+        _ if reserved => format!("_{}", stem),
+        _ => stem.to_string(),
+    }
+}
+
+/// `<directory>/<stem>.<extension>`, or the first of `<stem> (2).<extension>`,
+/// `<stem> (3).<extension>`… that does not exist yet.
+pub fn unique_path(directory: &Path, stem: &str, extension: &str) -> PathBuf {
+    let mut path = directory.join(format!("{}.{}", stem, extension));
+    let mut copy = 2;
+
+    while path.exists() {
+        path = directory.join(format!("{} ({}).{}", stem, copy, extension));
+        copy += 1;
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_reserved_characters() {
+        assert_eq!(
+            sanitize_file_name("Анна: \"Каренина\"", "x"),
+            "Анна_ _Каренина_"
+        );
+        assert_eq!(sanitize_file_name("a/b\\c|d?e*f<g>", "x"), "a_b_c_d_e_f_g_");
+        assert_eq!(sanitize_file_name("tab\there\u{7}", "x"), "tab here_");
+        assert_eq!(sanitize_file_name("  many   spaces  ", "x"), "many spaces");
+    }
+
+    #[test]
+    fn escapes_reserved_names() {
+        assert_eq!(sanitize_file_name("CON", "x"), "_CON");
+        assert_eq!(sanitize_file_name("nul", "x"), "_nul");
+        assert_eq!(sanitize_file_name("Com1.txt", "x"), "_Com1.txt");
+        assert_eq!(sanitize_file_name("LPT9 .pdf", "x"), "_LPT9 .pdf");
+        assert_eq!(sanitize_file_name("CONSOLE", "x"), "CONSOLE");
+        assert_eq!(sanitize_file_name("COM10", "x"), "COM10");
+    }
+
+    #[test]
+    fn drops_leading_and_trailing_dots() {
+        assert_eq!(sanitize_file_name("Анна...", "x"), "Анна");
+        assert_eq!(sanitize_file_name("Анна. . ", "x"), "Анна");
+        assert_eq!(sanitize_file_name(".hidden", "x"), "hidden");
+        assert_eq!(sanitize_file_name(" . . ", "mandala"), "mandala");
+        assert_eq!(sanitize_file_name("", "mandala"), "mandala");
+    }
+
+    #[test]
+    fn cuts_long_names() {
+        let name = "я".repeat(MAX_STEM_LENGTH + 20);
+
+        assert_eq!(
+            sanitize_file_name(&name, "x").chars().count(),
+            MAX_STEM_LENGTH
+        );
+        assert_eq!(
+            sanitize_file_name(&format!("{}.", "я".repeat(MAX_STEM_LENGTH - 1)), "x"),
+            "я".repeat(MAX_STEM_LENGTH - 1)
+        );
+    }
+
+    #[test]
+    fn numbers_existing_paths() {
+        let directory = std::env::temp_dir().join(format!("mandala-files-{}", std::process::id()));
+
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let first = unique_path(&directory, "Анна", "pdf");
+
+        assert_eq!(first, directory.join("Анна.pdf"));
+        std::fs::write(&first, b"").unwrap();
+
+        let second = unique_path(&directory, "Анна", "pdf");
+
+        assert_eq!(second, directory.join("Анна (2).pdf"));
+        std::fs::write(&second, b"").unwrap();
+
+        assert_eq!(
+            unique_path(&directory, "Анна", "pdf"),
+            directory.join("Анна (3).pdf")
+        );
+        assert_eq!(
+            unique_path(&directory, "Анна", "png"),
+            directory.join("Анна.png")
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
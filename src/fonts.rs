@@ -0,0 +1,294 @@
+//! Fonts embedded into the exported documents.
+//!
+//! The digits and the caption are set in Roboto Light unless another font
+//! is chosen for them, either a TrueType or OpenType file or a font
+//! installed in the system. Characters of the caption its font cannot draw
+//! are taken from the fallback fonts, then from Roboto and at last from any
+//! installed font that has them.
+
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use fontdb::{Database, Family, Query, Style, Weight};
+use ttf_parser::Face;
+
+use crate::error::{Error, Result};
+
+pub(crate) static ROBOTO_FONT: &[u8] = include_bytes!("./Roboto-Light.ttf");
+
+/// Where a font comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FontSource {
+    /// Roboto Light, bundled with the application.
+    #[default]
+    Roboto,
+    /// A `.ttf`, `.otf` or `.ttc` file, the first face of a collection.
+    File(PathBuf),
+    /// A font installed in the system, by its family name.
+    Installed(String),
+}
+
+impl FontSource {
+    pub(crate) fn load(&self) -> Result<LoadedFont> {
+        let (data, index) = match self {
+            FontSource::Roboto => (Cow::Borrowed(ROBOTO_FONT), 0),
+            FontSource::File(path) => {
+                let data = std::fs::read(path).map_err(|source| Error::ReadFont {
+                    path: path.clone(),
+                    source,
+                })?;
+
+                (Cow::Owned(data), 0)
+            }
+            FontSource::Installed(family) => {
+                let not_found = || Error::FontNotFound {
+                    family: family.clone(),
+                };
+                let mut database = Database::new();
+
+                database.load_system_fonts();
+
+                let id = database
+                    .query(&Query {
+                        families: &[Family::Name(family)],
+                        ..Query::default()
+                    })
+                    .ok_or_else(not_found)?;
+
+                database
+                    .with_face_data(id, |data, index| (Cow::Owned(data.to_vec()), index))
+                    .ok_or_else(not_found)?
+            }
+        };
+
+        let font = LoadedFont {
+            source: self.clone(),
+            data,
+            index,
+        };
+
+        font.face()?;
+
+        Ok(font)
+    }
+}
+
+/// A file when the value names an existing one or has a font extension, an
+/// installed family otherwise.
+impl FromStr for FontSource {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let path = Path::new(value);
+        let font_extension = path.extension().is_some_and(|extension| {
+            ["ttf", "otf", "ttc"]
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        });
+
+        Ok(if font_extension || path.is_file() {
+            FontSource::File(path.to_path_buf())
+        } else {
+            FontSource::Installed(value.to_string())
+        })
+    }
+}
+
+impl fmt::Display for FontSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontSource::Roboto => f.write_str("Roboto Light"),
+            FontSource::File(path) => match path.file_name() {
+                Some(name) => write!(f, "{}", name.to_string_lossy()),
+                None => write!(f, "{}", path.display()),
+            },
+            FontSource::Installed(family) => f.write_str(family),
+        }
+    }
+}
+
+/// Fonts of the text drawn on the mandala. Headings and the footer always
+/// use Roboto, the calculation pages the fonts of the caption.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Fonts {
+    /// Digits in the cells and in the legend of a colouring book.
+    pub digits: FontSource,
+    pub caption: FontSource,
+    /// Tried in order for the characters of the caption its font lacks.
+    pub fallback: Vec<FontSource>,
+}
+
+impl Fonts {
+    /// The caption font followed by the fonts to take the characters it
+    /// lacks from, ending with the installed fonts needed for `captions` and
+    /// `others`. A character of `others` no font has is left out instead of
+    /// failing.
+    pub(crate) fn caption_chain<'a>(
+        &self,
+        captions: impl IntoIterator<Item = &'a str>,
+        others: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Vec<LoadedFont>> {
+        let captions = captions.into_iter().collect::<Vec<&str>>();
+        let mut sources: Vec<&FontSource> = Vec::new();
+
+        for source in [&self.caption]
+            .into_iter()
+            .chain(&self.fallback)
+            .chain([&FontSource::Roboto])
+        {
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+
+        let mut chain = sources
+            .into_iter()
+            .map(FontSource::load)
+            .collect::<Result<Vec<LoadedFont>>>()?;
+        let faces = chain
+            .iter()
+            .map(LoadedFont::face)
+            .collect::<Result<Vec<Face>>>()?;
+        let mut missing = Vec::new();
+
+        for symbol in captions.iter().copied().chain(others).flat_map(str::chars) {
+            if is_drawn(symbol)
+                && !missing.contains(&symbol)
+                && !faces.iter().any(|face| has_outline(face, symbol))
+            {
+                missing.push(symbol);
+            }
+        }
+
+        chain.extend(find_installed(&mut missing));
+
+        let required = missing
+            .into_iter()
+            .find(|&symbol| captions.iter().any(|caption| caption.contains(symbol)));
+
+        match required {
+            Some(character) => Err(Error::UnsupportedCharacter { character }),
+            None => Ok(chain),
+        }
+    }
+}
+
+/// Whether `symbol` leaves a mark on the page. Spaces, controls and
+/// invisible formatting characters need no glyph.
+pub(crate) fn is_drawn(symbol: char) -> bool {
+    !symbol.is_whitespace() && !symbol.is_control() && !is_ignorable(symbol)
+}
+
+/// Invisible characters steering joining, direction or emoji
+/// presentation, left out of the drawn text.
+pub(crate) fn is_ignorable(symbol: char) -> bool {
+    matches!(
+        symbol,
+        '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{206F}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FEFF}'
+            | '\u{E0000}'..='\u{E0FFF}'
+    )
+}
+
+/// Whether `face` can draw `symbol`. Glyphs without outlines, such as
+/// bitmap emoji, cannot be embedded and do not count.
+pub(crate) fn has_outline(face: &Face, symbol: char) -> bool {
+    face.glyph_index(symbol)
+        .is_some_and(|glyph| face.glyph_bounding_box(glyph).is_some())
+}
+
+// Installed fonts drawing the `missing` characters, which are removed from
+// it as they are found. Regular faces are preferred, in the order of their
+// family names so the choice does not depend on the file system.
+fn find_installed(missing: &mut Vec<char>) -> Vec<LoadedFont> {
+    if missing.is_empty() {
+        return Vec::new();
+    }
+
+    let mut database = Database::new();
+
+    database.load_system_fonts();
+
+    let mut faces = database.faces().collect::<Vec<_>>();
+
+    faces.sort_by_key(|face| {
+        (
+            face.style != Style::Normal,
+            face.weight.0.abs_diff(Weight::NORMAL.0),
+            face.families.first().map(|family| family.0.clone()),
+            face.index,
+        )
+    });
+
+    let mut fonts = Vec::new();
+
+    for info in faces {
+        if missing.is_empty() {
+            break;
+        }
+
+        let family = match info.families.first() {
+            Some((family, _)) => family.clone(),
+            None => info.post_script_name.clone(),
+        };
+        let found = database.with_face_data(info.id, |data, index| {
+            let face = Face::parse(data, index).ok()?;
+            let covered = missing
+                .iter()
+                .copied()
+                .filter(|&symbol| has_outline(&face, symbol))
+                .collect::<Vec<char>>();
+
+            (!covered.is_empty()).then(|| (covered, data.to_vec(), index))
+        });
+
+        if let Some(Some((covered, data, index))) = found {
+            missing.retain(|symbol| !covered.contains(symbol));
+            fonts.push(LoadedFont {
+                source: FontSource::Installed(family),
+                data: Cow::Owned(data),
+                index,
+            });
+        }
+    }
+
+    fonts
+}
+
+/// Font data read into memory.
+pub(crate) struct LoadedFont {
+    pub source: FontSource,
+    pub data: Cow<'static, [u8]>,
+    /// Face within a font collection.
+    pub index: u32,
+}
+
+impl LoadedFont {
+    pub fn face(&self) -> Result<Face<'_>> {
+        Face::parse(&self.data, self.index).map_err(|_| Error::FontParse)
+    }
+
+    /// Fails on the first visible character of `text` the font has no
+    /// glyph for.
+    pub fn check(&self, text: &str) -> Result<()> {
+        let face = self.face()?;
+
+        match text
+            .chars()
+            .filter(|&symbol| is_drawn(symbol))
+            .find(|&symbol| face.glyph_index(symbol).is_none())
+        {
+            Some(character) => Err(Error::MissingGlyph {
+                font: self.source.to_string(),
+                character,
+            }),
+            None => Ok(()),
+        }
+    }
+}
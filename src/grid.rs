@@ -0,0 +1,100 @@
+/// A single cell of the mandala triangle.
+///
+/// `row` counts from the apex (row `0` holds a single cell), `col` counts
+/// from the left edge of the row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Cell {
+    pub row: usize,
+    pub col: usize,
+    pub value: u16,
+}
+
+/// The triangle produced by [`calculate_mandala`](crate::calculate_mandala).
+///
+/// Rows are stored apex first, so row `n` always holds `n + 1` cells and the
+/// last row is the mirrored seed line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MandalaGrid {
+    rows: Vec<Vec<u16>>,
+    modulus: u16,
+}
+
+impl MandalaGrid {
+    pub(crate) fn new(rows: Vec<Vec<u16>>, modulus: u16) -> Self {
+        debug_assert!(
+            rows.iter()
+                .enumerate()
+                .all(|(index, row)| row.len() == index + 1)
+        );
+
+        Self { rows, modulus }
+    }
+
+    /// Every value lies in `1..=modulus`.
+    pub fn modulus(&self) -> u16 {
+        self.modulus
+    }
+
+    /// Number of rows, which is also the width of the base line.
+    pub fn size(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The digits the mandala was grown from, i.e. the left half of the line.
+    pub fn seed(&self) -> &[u16] {
+        &self.line()[..self.size() / 2]
+    }
+
+    /// The widest row: the seed followed by its mirror image.
+    pub fn line(&self) -> &[u16] {
+        self.rows.last().unwrap()
+    }
+
+    /// The single cell every other row reduces to.
+    pub fn apex(&self) -> u16 {
+        self.rows[0][0]
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[u16]> {
+        self.rows.get(row).map(Vec::as_slice)
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[u16]> {
+        self.rows.iter().map(Vec::as_slice)
+    }
+
+    /// Values of column `col` from the first row that has it down to the line.
+    pub fn column(&self, col: usize) -> impl Iterator<Item = u16> + '_ {
+        self.rows.iter().skip(col).map(move |row| row[col])
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<u16> {
+        self.rows
+            .get(row)
+            .and_then(|values| values.get(col))
+            .copied()
+    }
+
+    /// How many cells hold each value, starting with `1`.
+    pub fn counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; usize::from(self.modulus)];
+
+        for cell in self.cells() {
+            counts[usize::from(cell.value) - 1] += 1;
+        }
+
+        counts
+    }
+
+    /// Every cell with its coordinates, row by row starting from the apex.
+    pub fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.rows.iter().enumerate().flat_map(|(row, values)| {
+            values
+                .iter()
+                .enumerate()
+                .map(move |(col, &value)| Cell { row, col, value })
+        })
+    }
+}
@@ -0,0 +1,54 @@
+//! Traditional meanings of the digits, printed in the report.
+
+/// What a digit stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Meaning {
+    /// A few words to put next to the digit.
+    pub keywords: &'static str,
+    pub description: &'static str,
+}
+
+const MEANINGS: [Meaning; 9] = [
+    Meaning {
+        keywords: "воля, начало, лидерство",
+        description: "Энергия первого шага: самостоятельность, решительность и стремление вести за собой.",
+    },
+    Meaning {
+        keywords: "партнёрство, чуткость, равновесие",
+        description: "Умение слышать других, дипломатия и поиск согласия в отношениях.",
+    },
+    Meaning {
+        keywords: "творчество, общение, радость",
+        description: "Самовыражение, лёгкость, воображение и дар слова.",
+    },
+    Meaning {
+        keywords: "порядок, труд, надёжность",
+        description: "Основательность, терпение и умение доводить дело до конца.",
+    },
+    Meaning {
+        keywords: "свобода, перемены, любознательность",
+        description: "Жажда нового опыта, подвижность и открытость миру.",
+    },
+    Meaning {
+        keywords: "забота, семья, ответственность",
+        description: "Гармония дома, готовность помогать и чувство долга перед близкими.",
+    },
+    Meaning {
+        keywords: "мудрость, поиск, внутренний мир",
+        description: "Склонность к размышлениям, интуиция и стремление к знанию.",
+    },
+    Meaning {
+        keywords: "сила, достижение, достаток",
+        description: "Деловая хватка, выносливость и умение распоряжаться ресурсами.",
+    },
+    Meaning {
+        keywords: "сострадание, завершение, служение",
+        description: "Широта взглядов, щедрость и стремление помогать миру.",
+    },
+];
+
+/// The meaning of `digit`, known for 1 to 9 only.
+pub fn meaning(digit: u16) -> Option<Meaning> {
+    MEANINGS.get(usize::from(digit).checked_sub(1)?).copied()
+}
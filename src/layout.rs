@@ -0,0 +1,89 @@
+//! Geometry of the hexagonal drawing shared by the renderers.
+//!
+//! Every renderer draws six copies of the triangle, rotated by 60° around
+//! the centre. Coordinates are in millimetres with the y axis pointing up.
+
+use glam::{Mat2, Vec2};
+
+use crate::grid::Cell;
+
+pub(crate) struct Sizes {
+    pub size: usize,
+    pub segment_size: f32,
+    pub half_segment_size: f32,
+    pub line_offset: f32,
+    pub half_line_offset: f32,
+    pub quarter_segment_size: f32,
+    pub translation_vector: Vec2,
+}
+
+impl Sizes {
+    /// A mandala of `size` rows fitting into a circle of `width` diameter
+    /// around `center`.
+    pub fn new(size: usize, width: f32, center: Vec2) -> Self {
+        let segment_size = width / (size * 2 + 1) as f32;
+        let half_segment_size = segment_size / 2.0;
+        let line_offset =
+            ((half_segment_size * 2.0).powf(2.0) - half_segment_size.powf(2.0)).sqrt();
+
+        Self {
+            size,
+            segment_size,
+            half_segment_size,
+            line_offset,
+            half_line_offset: line_offset / 2.0,
+            quarter_segment_size: half_segment_size / 2.0,
+            translation_vector: center,
+        }
+    }
+
+    fn place(&self, point: Vec2, segment: usize) -> Vec2 {
+        Mat2::from_angle((segment as f32) * 60.0_f32.to_radians()) * point + self.translation_vector
+    }
+
+    /// Centre of `cell` in the copy of the triangle numbered `segment`.
+    pub fn cell_center(&self, cell: Cell, segment: usize) -> Vec2 {
+        let x = (cell.col as f32) * self.line_offset + self.half_line_offset;
+        let y = (cell.row as f32) * self.segment_size + self.half_segment_size
+            - (cell.col as f32) * self.half_segment_size
+            + self.quarter_segment_size;
+
+        self.place(Vec2::new(x, y), segment)
+    }
+
+    /// Corners of the rhombus of `cell`, counter-clockwise from the bottom
+    /// left one before rotation.
+    pub fn cell_corners(&self, cell: Cell, segment: usize) -> [Vec2; 4] {
+        let left = (cell.col as f32) * self.line_offset;
+        let right = left + self.line_offset;
+        let bottom =
+            (cell.row as f32) * self.segment_size - (cell.col as f32) * self.half_segment_size;
+
+        [
+            Vec2::new(left, bottom),
+            Vec2::new(right, bottom + self.half_segment_size),
+            Vec2::new(right, bottom + self.half_segment_size + self.segment_size),
+            Vec2::new(left, bottom + self.segment_size),
+        ]
+        .map(|corner| self.place(corner, segment))
+    }
+
+    /// Start and end of every grid line.
+    pub fn lines(&self) -> impl Iterator<Item = [Vec2; 2]> + '_ {
+        (0..6).flat_map(move |segment| {
+            (0..=self.size).flat_map(move |line_index| {
+                let x = self.line_offset * line_index as f32;
+                let y1 = self.half_segment_size * (line_index as f32);
+                let idx = if line_index == 0 { 0 } else { line_index - 1 };
+                let count = self.size - idx;
+                let y2 = y1 + (count as f32) * self.half_segment_size * 2.0;
+
+                [
+                    [Vec2::new(x, y1), Vec2::new(x, y2)],
+                    [Vec2::new(-x, y1), Vec2::new(-x, y2)],
+                ]
+                .map(|[start, end]| [self.place(start, segment), self.place(end, segment)])
+            })
+        })
+    }
+}
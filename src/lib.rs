@@ -0,0 +1,44 @@
+//! Mandala calculation engine shared by the desktop application and other tools.
+
+pub mod alphabet;
+pub mod calculation;
+#[cfg(any(feature = "pdf", feature = "svg"))]
+pub mod caption;
+pub mod date;
+pub mod error;
+pub mod files;
+#[cfg(any(feature = "pdf", feature = "svg"))]
+pub mod fonts;
+pub mod grid;
+pub mod interpretation;
+#[cfg(any(feature = "pdf", feature = "svg"))]
+mod layout;
+pub mod normalize;
+pub mod numbers;
+pub mod numerology;
+pub mod palette;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+#[cfg(feature = "raster")]
+pub mod raster;
+#[cfg(feature = "svg")]
+pub mod svg;
+#[cfg(any(feature = "pdf", feature = "svg"))]
+mod text;
+pub mod trace;
+pub mod transliteration;
+
+pub use alphabet::Alphabet;
+pub use calculation::{
+    CalculationOptions, SEED_WIDTHS, Token, analyse_text, calculate_date_mandala,
+    calculate_date_mandala_with_trace, calculate_mandala, calculate_mandala_with_trace,
+};
+pub use date::BirthDate;
+pub use error::{Error, Result};
+pub use grid::{Cell, MandalaGrid};
+pub use normalize::{Normalization, NormalizationForm};
+pub use numbers::NumberMode;
+pub use numerology::NumerologySystem;
+pub use palette::{Palette, Rgb};
+pub use trace::Trace;
+pub use transliteration::{TransliterationScheme, transliterate};
@@ -1,41 +1,91 @@
-mod calculation;
-// mod export;
-mod pdf;
-
-use calculation::calculate_mandala;
-// use export::save_mandala_pdf;
 use iced::widget::canvas::{Cache, Canvas, Geometry, Program, Text};
-use iced::widget::{TextInput, button, column, container, row, text};
+use iced::widget::{
+    TextInput, button, checkbox, column, container, pick_list, radio, row, scrollable, text,
+};
 use iced::{
     Color, Element, Fill, Pixels, Point, Rectangle, Renderer, Result as IcedResult, Size, Task,
-    Theme, Vector, alignment, application, color, mouse,
+    Theme, Vector, alignment, application, mouse,
+};
+use mandala::alphabet::{self, DEFAULT_ALPHABETS};
+use mandala::calculation::{DEFAULT_MODULUS, DEFAULT_WIDTH, MODULI};
+use mandala::caption::{CaptionOptions, CaptionPosition};
+use mandala::files::{sanitize_file_name, unique_path};
+use mandala::fonts::{FontSource, Fonts};
+use mandala::pdf::{
+    CellColoring, CellMarks, ColoringBook, Orientation, PageSize, PdfOptions, save_mandala_pdf,
+};
+use mandala::raster::{RasterFormat, RasterOptions, Resolution, save_mandala_raster};
+use mandala::svg::save_mandala_svg;
+use mandala::{
+    Alphabet, BirthDate, CalculationOptions, Error, MandalaGrid, Normalization, NormalizationForm,
+    NumberMode, NumerologySystem, Palette, Rgb, SEED_WIDTHS, Token, Trace, TransliterationScheme,
+    analyse_text, calculate_date_mandala_with_trace, calculate_mandala_with_trace, transliterate,
 };
 use opener::reveal;
-use pdf::save_mandala_pdf;
-use std::path::PathBuf;
-
-static COLORS: [Color; 9] = [
-    color!(0xE6194B),
-    color!(0xF58231),
-    color!(0xFFE119),
-    color!(0xBFEF45),
-    color!(0x3CB44B),
-    color!(0x42D4F4),
-    color!(0x4363D8),
-    color!(0x911EB4),
-    color!(0xF032E6),
-];
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 static TEXT_SIZE: Pixels = Pixels(24.0);
+// Text never takes more than this share of a block
+const TEXT_BLOCK_RATIO: f32 = 0.6;
+// Submitting asks for confirmation when more characters than this are ignored
+const IGNORED_LIMIT: usize = 3;
+const IGNORED_COLOR: Color = Color::from_rgb(0.85, 0.2, 0.2);
+// Page margins offered for the PDF, in millimetres
+const MARGINS: [u16; 5] = [0, 5, 10, 15, 20];
+const DEFAULT_MARGIN: u16 = 10;
+// Line widths offered for colouring books, in points
+const LINE_WIDTHS: [f32; 4] = [0.8, 1.2, 1.6, 2.0];
+// Caption sizes offered, in points, and how many lines it may take
+const CAPTION_SIZES: [f32; 6] = [10.0, 12.0, 14.0, 18.0, 24.0, 32.0];
+const CAPTION_LINES: [usize; 3] = [1, 2, 3];
 
 #[derive(Debug, Clone)]
 enum Message {
     Type(String),
+    SelectMode(InputMode),
+    ToggleAlphabet(usize, bool),
+    SelectSystem(NumerologySystem),
+    SelectWidth(usize),
+    SelectModulus(u16),
+    SelectNumbers(NumberMode),
+    SelectNormalizationForm(NormalizationForm),
+    ToggleYoAsYe(bool),
+    ToggleStripDiacritics(bool),
+    ToggleFoldSharpS(bool),
+    ToggleTransliteration(bool),
+    SelectScheme(TransliterationScheme),
+    SelectSpelling(Spelling),
     Submit,
     Return,
-    Export,
+    ToggleTrace,
+    ToggleTraceExport(bool),
+    SelectColoring(CellColoring),
+    ToggleColoringBook(bool),
+    SelectMarks(CellMarks),
+    SelectLineWidth(f32),
+    SelectPageSize(PageSize),
+    SelectOrientation(Orientation),
+    SelectMargin(u16),
+    SelectRasterFormat(RasterFormat),
+    SelectResolution(Resolution),
+    SelectBackground(Background),
+    ChooseFont(FontTarget),
+    FontChosen(FontTarget, Option<PathBuf>),
+    ResetFonts,
+    SelectCaptionPosition(CaptionPosition),
+    SelectCaptionSize(f32),
+    SelectCaptionLines(usize),
+    TypeSubtitle(String),
+    TypeCaptionDate(String),
+    TypeDedication(String),
+    Export(ExportFormat),
+    ExportPathChosen(ExportFormat, Option<PathBuf>),
+    ChooseExportDir,
+    ExportDirChosen(Option<PathBuf>),
     Open,
-    Exported(Result<PathBuf, String>),
+    Exported(Result<PathBuf, Arc<Error>>),
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -44,22 +94,127 @@ enum Screen {
     Result,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum InputMode {
+    Text,
+    Date,
+}
+
+impl InputMode {
+    const ALL: [InputMode; 2] = [InputMode::Text, InputMode::Date];
+}
+
+impl fmt::Display for InputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InputMode::Text => "Имя или текст",
+            InputMode::Date => "Дата рождения",
+        })
+    }
+}
+
+// Which spelling of the text is calculated when transliteration is on
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Spelling {
+    Original,
+    Transliterated,
+}
+
+#[derive(Debug, Clone)]
+struct AlphabetChoice {
+    alphabet: Alphabet,
+    enabled: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ExportFormat {
+    Pdf,
+    /// A PDF with the triangle, the calculation and the statistics pages.
+    Report,
+    Svg,
+    Raster(RasterFormat),
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Pdf | ExportFormat::Report => "pdf",
+            ExportFormat::Svg => "svg",
+            ExportFormat::Raster(format) => format.extension(),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportFormat::Pdf | ExportFormat::Report => f.write_str("PDF"),
+            ExportFormat::Svg => f.write_str("SVG"),
+            ExportFormat::Raster(format) => format.fmt(f),
+        }
+    }
+}
+
+// Text a font can be chosen for
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum FontTarget {
+    Digits,
+    Caption,
+}
+
+// Backgrounds offered for images
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Background {
+    Transparent,
+    White,
+    Black,
+}
+
+impl Background {
+    const ALL: [Background; 3] = [
+        Background::Transparent,
+        Background::White,
+        Background::Black,
+    ];
+
+    fn color(&self) -> Option<Rgb> {
+        match self {
+            Background::Transparent => None,
+            Background::White => Some(Rgb::from_hex(0xFFFFFF)),
+            Background::Black => Some(Rgb::from_hex(0x000000)),
+        }
+    }
+}
+
+impl fmt::Display for Background {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Background::Transparent => "Прозрачный фон",
+            Background::White => "Белый фон",
+            Background::Black => "Чёрный фон",
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 enum ExportState {
     Idle,
+    Choosing,
     Saving,
-    Completed(Result<PathBuf, String>),
+    Completed(Result<PathBuf, Arc<Error>>),
 }
 
 struct Mandala<'a> {
-    result: &'a Vec<Vec<u16>>,
+    result: &'a MandalaGrid,
+    palette: Palette,
     cache: Cache<Renderer>,
 }
 
 impl<'a> Mandala<'a> {
-    fn new(result: &'a Vec<Vec<u16>>) -> Self {
+    fn new(result: &'a MandalaGrid) -> Self {
         Self {
             result,
+            palette: Palette::for_modulus(result.modulus()),
             cache: Cache::default(),
         }
     }
@@ -80,32 +235,31 @@ impl<'a> Program<Message> for Mandala<'a> {
             let side = f32::min(frame.width(), frame.height());
             let padding_x = (frame.width() - side) / 2.0;
             let padding_y = (frame.height() - side) / 2.0;
-            let block_size = side / 16.0;
+            let size = self.result.size();
+            let block_size = side / size as f32;
             let block_half_size = block_size / 2.0;
             let text_offset: Vector = [block_half_size, block_half_size].into();
 
-            for row in 0..16 {
-                let block_padding = (side - (row as f32 + 1.0) * block_size) / 2.0;
-                for col in 0..row + 1 {
-                    let x = padding_x + block_padding + (col as f32) * block_size;
-                    let y = padding_y + ((15 - row) as f32) * block_size;
-                    let index = self.result[15 - row][col];
-                    let color = COLORS.get((index - 1) as usize).unwrap();
-                    let top_left_point = Point::new(x, y);
-
-                    let text = Text {
-                        content: index.to_string(),
-                        position: top_left_point + text_offset,
-                        color: Color::BLACK,
-                        horizontal_alignment: alignment::Horizontal::Center,
-                        vertical_alignment: alignment::Vertical::Center,
-                        size: TEXT_SIZE,
-                        ..Text::default()
-                    };
+            for cell in self.result.cells() {
+                let block_padding = (side - (cell.row as f32 + 1.0) * block_size) / 2.0;
+                let x = padding_x + block_padding + (cell.col as f32) * block_size;
+                let y = padding_y + ((size - 1 - cell.row) as f32) * block_size;
+                let rgb = self.palette.color(cell.value);
+                let color = Color::from_rgb8(rgb.r, rgb.g, rgb.b);
+                let top_left_point = Point::new(x, y);
 
-                    frame.fill_rectangle(top_left_point, Size::new(block_size, block_size), *color);
-                    frame.fill_text(text);
-                }
+                let text = Text {
+                    content: cell.value.to_string(),
+                    position: top_left_point + text_offset,
+                    color: Color::BLACK,
+                    horizontal_alignment: alignment::Horizontal::Center,
+                    vertical_alignment: alignment::Vertical::Center,
+                    size: Pixels(f32::min(TEXT_SIZE.0, block_size * TEXT_BLOCK_RATIO)),
+                    ..Text::default()
+                };
+
+                frame.fill_rectangle(top_left_point, Size::new(block_size, block_size), color);
+                frame.fill_text(text);
             }
         });
 
@@ -113,49 +267,417 @@ impl<'a> Program<Message> for Mandala<'a> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 struct State {
     screen: Screen,
+    mode: InputMode,
     input: String,
-    calculation: Result<Vec<Vec<u16>>, String>,
+    caption: String,
+    alphabets: Vec<AlphabetChoice>,
+    alphabet_errors: Vec<Error>,
+    system: NumerologySystem,
+    width: usize,
+    modulus: u16,
+    numbers: NumberMode,
+    normalization: Normalization,
+    confirm_ignored: bool,
+    transliterate: bool,
+    scheme: TransliterationScheme,
+    spelling: Spelling,
+    other_spelling: Option<String>,
+    calculation: Option<Result<MandalaGrid, Error>>,
+    trace: Option<Trace>,
+    show_trace: bool,
+    export_trace: bool,
+    pdf: PdfOptions,
+    coloring_book: ColoringBook,
+    margin: u16,
+    raster: RasterOptions,
+    background: Background,
+    fonts: Fonts,
+    caption_options: CaptionOptions,
     export: ExportState,
+    export_dir: PathBuf,
 }
 
 impl State {
+    fn calculation_options(&self) -> CalculationOptions {
+        CalculationOptions {
+            alphabets: self
+                .alphabets
+                .iter()
+                .filter(|choice| choice.enabled)
+                .map(|choice| choice.alphabet.clone())
+                .collect(),
+            system: self.system,
+            width: self.width,
+            modulus: self.modulus,
+            numbers: self.numbers,
+            normalization: self.normalization,
+        }
+    }
+
+    // Text to calculate and, with transliteration on, the other spelling
+    fn spellings(&self) -> (String, Option<String>) {
+        if !self.transliterate || self.mode != InputMode::Text {
+            return (self.input.to_owned(), None);
+        }
+
+        let converted = transliterate(&self.input, self.scheme);
+
+        match self.spelling {
+            Spelling::Original => (self.input.to_owned(), Some(converted)),
+            Spelling::Transliterated => (converted, Some(self.input.to_owned())),
+        }
+    }
+
+    // How the text to calculate is read, empty for dates
+    fn tokens(&self) -> Vec<Token> {
+        match self.mode {
+            InputMode::Text => {
+                analyse_text(&self.spellings().0, &self.calculation_options()).unwrap_or_default()
+            }
+            InputMode::Date => Vec::new(),
+        }
+    }
+
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::Type(text) => {
                 self.input = text;
+                self.confirm_ignored = false;
+
+                Task::none()
+            }
+            Message::SelectMode(mode) => {
+                self.mode = mode;
+                self.confirm_ignored = false;
+
+                Task::none()
+            }
+            Message::ToggleAlphabet(index, enabled) => {
+                if let Some(choice) = self.alphabets.get_mut(index) {
+                    choice.enabled = enabled;
+                }
+
+                Task::none()
+            }
+            Message::SelectSystem(system) => {
+                self.system = system;
+
+                Task::none()
+            }
+            Message::SelectWidth(width) => {
+                self.width = width;
+
+                Task::none()
+            }
+            Message::SelectModulus(modulus) => {
+                self.modulus = modulus;
+
+                Task::none()
+            }
+            Message::SelectNumbers(numbers) => {
+                self.numbers = numbers;
+
+                Task::none()
+            }
+            Message::SelectNormalizationForm(form) => {
+                self.normalization.form = form;
+
+                Task::none()
+            }
+            Message::ToggleYoAsYe(enabled) => {
+                self.normalization.yo_as_ye = enabled;
+
+                Task::none()
+            }
+            Message::ToggleStripDiacritics(enabled) => {
+                self.normalization.strip_diacritics = enabled;
+
+                Task::none()
+            }
+            Message::ToggleFoldSharpS(enabled) => {
+                self.normalization.fold_sharp_s = enabled;
+
+                Task::none()
+            }
+            Message::ToggleTransliteration(enabled) => {
+                self.transliterate = enabled;
+                self.confirm_ignored = false;
+
+                Task::none()
+            }
+            Message::SelectScheme(scheme) => {
+                self.scheme = scheme;
+
+                Task::none()
+            }
+            Message::SelectSpelling(spelling) => {
+                self.spelling = spelling;
+                self.confirm_ignored = false;
 
                 Task::none()
             }
             Message::Submit => {
+                let ignored = self
+                    .tokens()
+                    .iter()
+                    .filter(|token| token.is_ignored())
+                    .count();
+
+                if ignored > IGNORED_LIMIT && !self.confirm_ignored {
+                    self.confirm_ignored = true;
+
+                    return Task::none();
+                }
+
                 self.screen = Screen::Result;
-                self.calculation = calculate_mandala(&self.input);
+                self.confirm_ignored = false;
+
+                let options = self.calculation_options();
+                let result = match self.mode {
+                    InputMode::Text => {
+                        let (spelling, other_spelling) = self.spellings();
+
+                        self.caption = spelling;
+                        self.other_spelling = other_spelling;
+                        calculate_mandala_with_trace(&self.caption, &options)
+                    }
+                    InputMode::Date => BirthDate::parse(&self.input).and_then(|date| {
+                        self.caption = date.to_string();
+                        calculate_date_mandala_with_trace(&date, &options)
+                    }),
+                };
+
+                self.calculation = Some(result.map(|(grid, trace)| {
+                    self.trace = Some(trace);
+                    grid
+                }));
 
                 Task::none()
             }
             Message::Return => {
                 self.screen = Screen::Input;
+                self.other_spelling = None;
+                self.confirm_ignored = false;
                 self.input = "".to_string();
-                self.calculation = Err("Введите текст для мандалы".to_string());
+                self.calculation = None;
+                self.trace = None;
+                self.show_trace = false;
                 self.export = ExportState::Idle;
 
                 Task::none()
             }
-            Message::Export => {
+            Message::ToggleTrace => {
+                self.show_trace = !self.show_trace;
+
+                Task::none()
+            }
+            Message::ToggleTraceExport(enabled) => {
+                self.export_trace = enabled;
+
+                Task::none()
+            }
+            Message::SelectColoring(coloring) => {
+                self.pdf.coloring = coloring;
+
+                Task::none()
+            }
+            Message::ToggleColoringBook(enabled) => {
+                self.pdf.coloring_book = Some(self.coloring_book).filter(|_| enabled);
+
+                Task::none()
+            }
+            Message::SelectMarks(marks) => {
+                self.coloring_book.marks = marks;
+                self.pdf.coloring_book = self.pdf.coloring_book.map(|_| self.coloring_book);
+
+                Task::none()
+            }
+            Message::SelectLineWidth(line_width) => {
+                self.coloring_book.line_width = line_width;
+                self.pdf.coloring_book = self.pdf.coloring_book.map(|_| self.coloring_book);
+
+                Task::none()
+            }
+            Message::SelectPageSize(page_size) => {
+                self.pdf.page_size = page_size;
+
+                Task::none()
+            }
+            Message::SelectOrientation(orientation) => {
+                self.pdf.orientation = orientation;
+
+                Task::none()
+            }
+            Message::SelectMargin(margin) => {
+                self.margin = margin;
+                self.pdf.margin = margin as f32;
+
+                Task::none()
+            }
+            Message::SelectRasterFormat(format) => {
+                self.raster.format = format;
+
+                Task::none()
+            }
+            Message::SelectResolution(resolution) => {
+                self.raster.resolution = resolution;
+
+                Task::none()
+            }
+            Message::SelectBackground(background) => {
+                self.background = background;
+                self.raster.background = background.color();
+
+                Task::none()
+            }
+            Message::ChooseFont(target) => Task::perform(
+                rfd::AsyncFileDialog::new()
+                    .set_title("Выберите шрифт")
+                    .add_filter("TrueType, OpenType", &["ttf", "otf", "ttc"])
+                    .pick_file(),
+                move |file| Message::FontChosen(target, file.map(|file| file.path().to_path_buf())),
+            ),
+            Message::FontChosen(target, path) => {
+                if let Some(path) = path {
+                    let font = FontSource::File(path);
+
+                    match target {
+                        FontTarget::Digits => self.fonts.digits = font,
+                        FontTarget::Caption => self.fonts.caption = font,
+                    }
+                }
+
+                Task::none()
+            }
+            Message::ResetFonts => {
+                self.fonts = Fonts::default();
+
+                Task::none()
+            }
+            Message::SelectCaptionPosition(position) => {
+                self.caption_options.position = position;
+
+                Task::none()
+            }
+            Message::SelectCaptionSize(font_size) => {
+                self.caption_options.font_size = font_size;
+
+                Task::none()
+            }
+            Message::SelectCaptionLines(max_lines) => {
+                self.caption_options.max_lines = max_lines;
+
+                Task::none()
+            }
+            Message::TypeSubtitle(subtitle) => {
+                self.caption_options.subtitle = subtitle;
+
+                Task::none()
+            }
+            Message::TypeCaptionDate(date) => {
+                self.caption_options.date = date;
+
+                Task::none()
+            }
+            Message::TypeDedication(dedication) => {
+                self.caption_options.dedication = dedication;
+
+                Task::none()
+            }
+            Message::Export(format) => {
+                self.export = ExportState::Choosing;
+
+                let stem = sanitize_file_name(&format!("Мандала {}", self.caption), "Мандала");
+                let suggested = unique_path(&self.export_dir, &stem, format.extension());
+
+                Task::perform(choose_export_path(suggested, format), move |path| {
+                    Message::ExportPathChosen(format, path)
+                })
+            }
+            Message::ExportPathChosen(format, path) => {
+                let (Some(path), Some(Ok(calculation))) = (path, &self.calculation) else {
+                    self.export = ExportState::Idle;
+
+                    return Task::none();
+                };
+
                 self.export = ExportState::Saving;
 
-                let calculation = self.calculation.to_owned().unwrap();
-                let input = self.input.to_owned();
+                let calculation = calculation.clone();
+                let caption = self.caption.to_owned();
 
-                Task::perform(save_mandala_pdf(calculation, input), Message::Exported)
+                match format {
+                    ExportFormat::Pdf | ExportFormat::Report => {
+                        let report = matches!(format, ExportFormat::Report);
+                        let system = Some(self.system).filter(|_| self.mode == InputMode::Text);
+                        let trace = self.trace.clone().filter(|_| self.export_trace || report);
+                        let options = PdfOptions {
+                            fonts: self.fonts.clone(),
+                            caption: self.caption_options.clone(),
+                            report,
+                            ..self.pdf.clone()
+                        };
+
+                        Task::perform(
+                            save_mandala_pdf(path, calculation, caption, system, trace, options),
+                            |result| Message::Exported(result.map_err(Arc::new)),
+                        )
+                    }
+                    ExportFormat::Svg => Task::perform(
+                        save_mandala_svg(
+                            path,
+                            calculation,
+                            caption,
+                            self.fonts.clone(),
+                            self.caption_options.clone(),
+                        ),
+                        |result| Message::Exported(result.map_err(Arc::new)),
+                    ),
+                    ExportFormat::Raster(format) => {
+                        let options = RasterOptions {
+                            format,
+                            fonts: self.fonts.clone(),
+                            caption: self.caption_options.clone(),
+                            ..self.raster.clone()
+                        };
+
+                        Task::perform(
+                            save_mandala_raster(path, calculation, caption, options),
+                            |result| Message::Exported(result.map_err(Arc::new)),
+                        )
+                    }
+                }
             }
             Message::Exported(result) => {
+                if let Ok(path) = &result
+                    && let Some(directory) = path.parent()
+                {
+                    self.export_dir = directory.to_path_buf();
+                    store_export_dir(directory);
+                }
+
                 self.export = ExportState::Completed(result);
 
                 Task::none()
             }
+            Message::ChooseExportDir => Task::perform(
+                rfd::AsyncFileDialog::new()
+                    .set_title("Папка для сохранения мандал")
+                    .set_directory(&self.export_dir)
+                    .pick_folder(),
+                |folder| Message::ExportDirChosen(folder.map(|folder| folder.path().to_path_buf())),
+            ),
+            Message::ExportDirChosen(directory) => {
+                if let Some(directory) = directory {
+                    store_export_dir(&directory);
+                    self.export_dir = directory;
+                }
+
+                Task::none()
+            }
             Message::Open => {
                 let path = match self.export {
                     ExportState::Completed(ref result) => result.as_ref().ok(),
@@ -175,25 +697,192 @@ impl State {
     fn view(&self) -> Element<'_, Message> {
         match self.screen {
             Screen::Input => {
-                let text = self.input.trim();
+                let trimmed = self.input.trim();
+
+                let date = Some(trimmed)
+                    .filter(|input| self.mode == InputMode::Date && !input.is_empty())
+                    .map(BirthDate::parse);
+
+                let can_submit = !trimmed.is_empty()
+                    && match self.mode {
+                        InputMode::Text => self.alphabets.iter().any(|choice| choice.enabled),
+                        InputMode::Date => matches!(date, Some(Ok(_))),
+                    };
+                let submit = Some(Message::Submit).filter(|_| can_submit);
+
+                let placeholder = match self.mode {
+                    InputMode::Text => "Пожалуйста, введите текст для мандалы",
+                    InputMode::Date => "Дата рождения: ДД.ММ.ГГГГ или ГГГГ-ММ-ДД",
+                };
+
+                let input = TextInput::new(placeholder, self.input.as_str())
+                    .on_input(Message::Type)
+                    .on_submit_maybe(submit.clone());
+
+                let transliteration_settings: Element<'_, Message> = row![
+                    checkbox("Транслитерация", self.transliterate)
+                        .on_toggle(Message::ToggleTransliteration),
+                    pick_list(
+                        TransliterationScheme::ALL,
+                        Some(self.scheme),
+                        Message::SelectScheme
+                    ),
+                ]
+                .spacing(10)
+                .align_y(alignment::Vertical::Center)
+                .into();
+
+                let spellings = (self.transliterate && !trimmed.is_empty()).then(|| {
+                    let converted = transliterate(&self.input, self.scheme);
+
+                    column![
+                        radio(
+                            format!("Как введено: {}", self.input),
+                            Spelling::Original,
+                            Some(self.spelling),
+                            Message::SelectSpelling
+                        ),
+                        radio(
+                            format!("Транслитерация: {}", converted),
+                            Spelling::Transliterated,
+                            Some(self.spelling),
+                            Message::SelectSpelling
+                        ),
+                    ]
+                    .spacing(5)
+                });
 
-                let input =
-                    TextInput::new("Пожалуйста, введите текст для мандалы", self.input.as_str())
-                        .on_input(Message::Type)
-                        .on_submit_maybe(if text.is_empty() {
-                            None
+                let tokens = self.tokens();
+                let ignored = tokens.iter().filter(|token| token.is_ignored()).count();
+
+                let preview = row(tokens.into_iter().map(|token| {
+                    let value = token
+                        .value
+                        .map(|value| value.to_string())
+                        .unwrap_or_default();
+                    let ignored = token.is_ignored();
+                    let symbol = text(token.text).size(20);
+
+                    column![
+                        if ignored {
+                            symbol.color(IGNORED_COLOR)
                         } else {
-                            Some(Message::Submit)
-                        });
+                            symbol
+                        },
+                        text(value).size(12),
+                    ]
+                    .align_x(alignment::Horizontal::Center)
+                    .into()
+                }))
+                .spacing(2)
+                .wrap();
 
-                let submit_button = button("Создать мандалу").on_press_maybe(if text.is_empty() {
-                    None
-                } else {
-                    Some(Message::Submit)
+                let ignored_notice = (ignored > 0).then(|| {
+                    let notice = if self.confirm_ignored {
+                        format!(
+                            "Не будет учтено символов: {}. Нажмите ещё раз, чтобы всё равно создать мандалу",
+                            ignored
+                        )
+                    } else {
+                        format!("Не будет учтено символов: {}", ignored)
+                    };
+
+                    text(notice).size(14).color(IGNORED_COLOR)
                 });
 
+                let submit_button = button(if self.confirm_ignored {
+                    "Всё равно создать"
+                } else {
+                    "Создать мандалу"
+                })
+                .on_press_maybe(submit);
+
+                let mode = row![
+                    text("Мандала из"),
+                    pick_list(InputMode::ALL, Some(self.mode), Message::SelectMode),
+                ]
+                .spacing(10)
+                .align_y(alignment::Vertical::Center);
+
+                let date_error = match date {
+                    Some(Err(error)) => Some(text(error.describe()).size(14)),
+                    _ => None,
+                };
+
+                let alphabets = row(self.alphabets.iter().enumerate().map(|(index, choice)| {
+                    checkbox(choice.alphabet.name(), choice.enabled)
+                        .on_toggle(move |enabled| Message::ToggleAlphabet(index, enabled))
+                        .into()
+                }))
+                .spacing(10)
+                .wrap();
+
+                let letter_settings: Element<'_, Message> = row![
+                    text("Система нумерологии"),
+                    pick_list(
+                        NumerologySystem::ALL,
+                        Some(self.system),
+                        Message::SelectSystem
+                    ),
+                    text("Числа"),
+                    pick_list(NumberMode::ALL, Some(self.numbers), Message::SelectNumbers),
+                ]
+                .spacing(10)
+                .align_y(alignment::Vertical::Center)
+                .into();
+
+                let normalization_settings: Element<'_, Message> = row![
+                    text("Нормализация"),
+                    pick_list(
+                        NormalizationForm::ALL,
+                        Some(self.normalization.form),
+                        Message::SelectNormalizationForm
+                    ),
+                    checkbox("ё как е", self.normalization.yo_as_ye)
+                        .on_toggle(Message::ToggleYoAsYe),
+                    checkbox("Убирать диакритику", self.normalization.strip_diacritics)
+                        .on_toggle(Message::ToggleStripDiacritics),
+                    checkbox("ß как ss", self.normalization.fold_sharp_s)
+                        .on_toggle(Message::ToggleFoldSharpS),
+                ]
+                .spacing(10)
+                .align_y(alignment::Vertical::Center)
+                .into();
+
+                let grid_settings: Element<'_, Message> = row![
+                    text("Цифр в строке"),
+                    pick_list(SEED_WIDTHS, Some(self.width), Message::SelectWidth),
+                    text("Основание"),
+                    pick_list(MODULI, Some(self.modulus), Message::SelectModulus),
+                ]
+                .spacing(10)
+                .align_y(alignment::Vertical::Center)
+                .into();
+
+                let alphabet_errors = column(
+                    self.alphabet_errors
+                        .iter()
+                        .map(|error| text(error.describe()).size(14).into()),
+                );
+
+                let content = match self.mode {
+                    InputMode::Text => column![mode, input, transliteration_settings]
+                        .push_maybe(spellings)
+                        .push(preview)
+                        .push_maybe(ignored_notice)
+                        .push(alphabets)
+                        .push(letter_settings)
+                        .push(normalization_settings)
+                        .push(grid_settings)
+                        .push(alphabet_errors),
+                    InputMode::Date => column![mode, input]
+                        .push_maybe(date_error)
+                        .push(grid_settings),
+                };
+
                 container(
-                    column![input, submit_button]
+                    content
+                        .push(submit_button)
                         .align_x(alignment::Horizontal::Right)
                         .spacing(10),
                 )
@@ -203,55 +892,384 @@ impl State {
                 .into()
             }
             Screen::Result => match &self.calculation {
-                Ok(result) => {
+                Some(Ok(result)) => {
                     let notification: Element<'_, Message> = match self.export {
-                        ExportState::Idle => {
-                            text("Нажмите «Сохранить» для сохранения мандалы в PDF").into()
-                        }
+                        ExportState::Idle => text(format!(
+                            "Нажмите «Сохранить» для сохранения мандалы в PDF, SVG или изображением, папка по умолчанию: {}",
+                            self.export_dir.display()
+                        ))
+                        .into(),
+                        ExportState::Choosing => text("Выберите, куда сохранить мандалу").into(),
                         ExportState::Saving => text("Сохранение...").into(),
                         ExportState::Completed(ref result) => match result {
                             Ok(path) => text(format!("Сохранено в {}", path.display())).into(),
-                            Err(error) => text(format!("Ошибка: {}", error)).into(),
+                            Err(error) => text(format!("Ошибка: {}", error.describe())).into(),
                         },
                     };
 
-                    let save_button = match self.export {
-                        ExportState::Completed(ref result) if result.is_ok() => {
-                            button("Открыть").on_press(Message::Open)
+                    let open_button = match self.export {
+                        ExportState::Completed(Ok(_)) => {
+                            Some(button("Открыть").on_press(Message::Open))
                         }
-                        _ => button("Сохранить").on_press(Message::Export),
+                        _ => None,
                     };
 
+                    let trace_button = button(if self.show_trace {
+                        "Скрыть расчёт"
+                    } else {
+                        "Как это посчитано"
+                    })
+                    .on_press(Message::ToggleTrace);
+
+                    let trace_panel: Option<Element<'_, Message>> = self
+                        .trace
+                        .as_ref()
+                        .filter(|_| self.show_trace)
+                        .map(|trace| view_trace(trace, result));
+
                     container(
-                        column![
-                            text(&self.input).size(20),
-                            Canvas::new(Mandala::new(result)).width(Fill).height(Fill),
-                            row![button("Назад").on_press(Message::Return), save_button,]
-                                .spacing(10),
-                            notification,
-                        ]
-                        .width(Fill)
-                        .spacing(10)
-                        .align_x(alignment::Horizontal::Center),
+                        column![text(&self.caption).size(20),]
+                            .push_maybe((self.mode == InputMode::Text).then(|| {
+                                text(format!("Система нумерологии: {}", self.system)).size(14)
+                            }))
+                            .push_maybe(self.other_spelling.as_ref().map(|spelling| {
+                                text(format!("Другое написание: {}", spelling)).size(14)
+                            }))
+                            .push_maybe(
+                                self.trace
+                                    .as_ref()
+                                    .and_then(|trace| trace.normalized.as_ref())
+                                    .map(|normalized| {
+                                        text(format!("Текст после нормализации: {}", normalized))
+                                            .size(14)
+                                    }),
+                            )
+                            .push(Canvas::new(Mandala::new(result)).width(Fill).height(Fill))
+                            .push_maybe(trace_panel)
+                            .push(
+                                row![
+                                    button("Назад").on_press(Message::Return),
+                                    trace_button,
+                                    button("Папка…").on_press(Message::ChooseExportDir),
+                                ]
+                                .push_maybe(open_button)
+                                .spacing(10)
+                                .align_y(alignment::Vertical::Center),
+                            )
+                            .push(
+                                row![
+                                    text("Шрифт цифр"),
+                                    button(text(self.fonts.digits.to_string()))
+                                        .on_press(Message::ChooseFont(FontTarget::Digits)),
+                                    text("Шрифт подписи"),
+                                    button(text(self.fonts.caption.to_string()))
+                                        .on_press(Message::ChooseFont(FontTarget::Caption)),
+                                ]
+                                .push_maybe(
+                                    (self.fonts != Fonts::default())
+                                        .then(|| button("Roboto").on_press(Message::ResetFonts)),
+                                )
+                                .spacing(10)
+                                .align_y(alignment::Vertical::Center),
+                            )
+                            .push(
+                                row![
+                                    pick_list(
+                                        CaptionPosition::ALL,
+                                        Some(self.caption_options.position),
+                                        Message::SelectCaptionPosition
+                                    ),
+                                    text("Размер, pt"),
+                                    pick_list(
+                                        CAPTION_SIZES,
+                                        Some(self.caption_options.font_size),
+                                        Message::SelectCaptionSize
+                                    ),
+                                    text("Строк не больше"),
+                                    pick_list(
+                                        CAPTION_LINES,
+                                        Some(self.caption_options.max_lines),
+                                        Message::SelectCaptionLines
+                                    ),
+                                    TextInput::new("Подзаголовок", &self.caption_options.subtitle)
+                                        .on_input(Message::TypeSubtitle),
+                                    TextInput::new("Дата", &self.caption_options.date)
+                                        .on_input(Message::TypeCaptionDate),
+                                    TextInput::new("Посвящение", &self.caption_options.dedication)
+                                        .on_input(Message::TypeDedication),
+                                ]
+                                .spacing(10)
+                                .align_y(alignment::Vertical::Center),
+                            )
+                            .push(
+                                row![
+                                    pick_list(
+                                        CellColoring::ALL,
+                                        Some(self.pdf.coloring),
+                                        Message::SelectColoring
+                                    ),
+                                    pick_list(
+                                        PageSize::PRESETS,
+                                        Some(self.pdf.page_size),
+                                        Message::SelectPageSize
+                                    ),
+                                    pick_list(
+                                        Orientation::ALL,
+                                        Some(self.pdf.orientation),
+                                        Message::SelectOrientation
+                                    ),
+                                    text("Поля, мм"),
+                                    pick_list(MARGINS, Some(self.margin), Message::SelectMargin),
+                                    checkbox("Добавить расчёт", self.export_trace)
+                                        .on_toggle(Message::ToggleTraceExport),
+                                    button("Сохранить PDF")
+                                        .on_press(Message::Export(ExportFormat::Pdf)),
+                                    button("Сохранить отчёт")
+                                        .on_press(Message::Export(ExportFormat::Report)),
+                                ]
+                                .spacing(10)
+                                .align_y(alignment::Vertical::Center),
+                            )
+                            .push(
+                                row![
+                                    checkbox("Раскраска", self.pdf.coloring_book.is_some())
+                                        .on_toggle(Message::ToggleColoringBook),
+                                ]
+                                .push_maybe(self.pdf.coloring_book.map(|book| {
+                                    row![
+                                        pick_list(
+                                            CellMarks::ALL,
+                                            Some(book.marks),
+                                            Message::SelectMarks
+                                        ),
+                                        text("Линии, пт"),
+                                        pick_list(
+                                            LINE_WIDTHS,
+                                            Some(book.line_width),
+                                            Message::SelectLineWidth
+                                        ),
+                                    ]
+                                    .spacing(10)
+                                    .align_y(alignment::Vertical::Center)
+                                }))
+                                .spacing(10)
+                                .align_y(alignment::Vertical::Center),
+                            )
+                            .push(
+                                row![
+                                    button("Сохранить SVG")
+                                        .on_press(Message::Export(ExportFormat::Svg)),
+                                    pick_list(
+                                        RasterFormat::ALL,
+                                        Some(self.raster.format),
+                                        Message::SelectRasterFormat
+                                    ),
+                                    pick_list(
+                                        Resolution::PRESETS,
+                                        Some(self.raster.resolution),
+                                        Message::SelectResolution
+                                    ),
+                                    pick_list(
+                                        Background::ALL,
+                                        Some(self.background),
+                                        Message::SelectBackground
+                                    ),
+                                    button("Сохранить изображение").on_press(Message::Export(
+                                        ExportFormat::Raster(self.raster.format)
+                                    )),
+                                ]
+                                .spacing(10)
+                                .align_y(alignment::Vertical::Center),
+                            )
+                            .push(notification)
+                            .width(Fill)
+                            .spacing(10)
+                            .align_x(alignment::Horizontal::Center),
                     )
                     .height(Fill)
                     .align_y(alignment::Vertical::Center)
                     .padding(20)
                     .into()
                 }
-                Err(error) => text(error).into(),
+                Some(Err(error)) => text(error.describe()).into(),
+                None => text("Введите текст для мандалы").into(),
             },
         }
     }
 }
 
+fn format_digits(digits: &[u16]) -> String {
+    digits
+        .iter()
+        .map(u16::to_string)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn view_trace<'a>(trace: &'a Trace, result: &'a MandalaGrid) -> Element<'a, Message> {
+    let letters = trace
+        .letters
+        .iter()
+        .map(|(letter, value)| format!("{} = {}", letter, value))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let mut steps = column![].spacing(5);
+
+    if !trace.substitutions.is_empty() {
+        let substitutions = trace
+            .substitutions
+            .iter()
+            .map(|substitution| format!("{} → {}", substitution.from, substitution.to))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        steps = steps
+            .push(text("Замены при нормализации").size(16))
+            .push(text(substitutions).size(14));
+    }
+
+    steps = steps
+        .push(text("Значения букв").size(16))
+        .push(text(letters).size(14));
+
+    if !trace.padding.is_empty() {
+        steps = steps.push(text("Дополнение короткого текста").size(16));
+        steps = steps.extend(trace.padding.iter().map(|step| {
+            text(format!("{} + {} = {}", step.left, step.right, step.sum))
+                .size(14)
+                .into()
+        }));
+    }
+
+    if !trace.reductions.is_empty() {
+        steps = steps.push(text("Свёртка длинного текста").size(16));
+        steps = steps.extend(
+            trace
+                .reductions
+                .iter()
+                .map(|row| text(format_digits(row)).size(14).into()),
+        );
+    }
+
+    steps = steps
+        .push(text("Исходная строка").size(16))
+        .push(text(format_digits(result.seed())).size(14));
+
+    scrollable(steps.width(Fill)).height(200).into()
+}
+
+// Asks where to save, starting from the suggested path
+async fn choose_export_path(suggested: PathBuf, format: ExportFormat) -> Option<PathBuf> {
+    let extension = format.extension();
+    let mut dialog = rfd::AsyncFileDialog::new()
+        .set_title("Сохранить мандалу")
+        .add_filter(format.to_string(), &[extension]);
+
+    if let Some(directory) = suggested.parent() {
+        dialog = dialog.set_directory(directory);
+    }
+    if let Some(file_name) = suggested.file_name() {
+        dialog = dialog.set_file_name(file_name.to_string_lossy());
+    }
+
+    let path = dialog.save_file().await?.path().to_path_buf();
+
+    Some(match path.extension() {
+        Some(current) if current.eq_ignore_ascii_case(extension) => path,
+        _ => path.with_extension(extension),
+    })
+}
+
+fn export_dir_setting() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("mandala").join("export-dir"))
+}
+
+// The default export directory is kept in `<config dir>/mandala/export-dir`,
+// falling back to the download directory and then to the home directory
+fn load_export_dir() -> PathBuf {
+    export_dir_setting()
+        .and_then(|setting| std::fs::read_to_string(setting).ok())
+        .map(|directory| PathBuf::from(directory.trim()))
+        .filter(|directory| directory.is_dir())
+        .or_else(dirs::download_dir)
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+// Failing to remember the directory only costs the preference
+fn store_export_dir(directory: &Path) {
+    if let Some(setting) = export_dir_setting() {
+        let _ = setting
+            .parent()
+            .map(std::fs::create_dir_all)
+            .transpose()
+            .and_then(|_| std::fs::write(&setting, directory.to_string_lossy().as_bytes()));
+    }
+}
+
+// Custom alphabets are read from `<config dir>/mandala/alphabets/*.txt`
+fn load_alphabets() -> (Vec<AlphabetChoice>, Vec<Error>) {
+    let mut choices = alphabet::builtin()
+        .iter()
+        .map(|alphabet| AlphabetChoice {
+            alphabet: alphabet.clone(),
+            enabled: DEFAULT_ALPHABETS.contains(&alphabet.id()),
+        })
+        .collect::<Vec<_>>();
+    let mut errors = Vec::new();
+
+    let custom = dirs::config_dir()
+        .map(|dir| alphabet::load_dir(&dir.join("mandala").join("alphabets")))
+        .unwrap_or_default();
+
+    for result in custom {
+        match result {
+            Ok(alphabet) => choices.push(AlphabetChoice {
+                alphabet,
+                enabled: false,
+            }),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    (choices, errors)
+}
+
 impl Default for State {
     fn default() -> Self {
+        let (alphabets, alphabet_errors) = load_alphabets();
+
         State {
             screen: Screen::Input,
+            mode: InputMode::Text,
             input: "".to_string(),
-            calculation: Err("Введите текст для мандалы".to_string()),
+            caption: "".to_string(),
+            alphabets,
+            alphabet_errors,
+            system: NumerologySystem::default(),
+            width: DEFAULT_WIDTH,
+            modulus: DEFAULT_MODULUS,
+            numbers: NumberMode::default(),
+            normalization: Normalization::default(),
+            confirm_ignored: false,
+            transliterate: false,
+            scheme: TransliterationScheme::default(),
+            spelling: Spelling::Transliterated,
+            other_spelling: None,
+            calculation: None,
+            trace: None,
+            show_trace: false,
+            export_trace: false,
+            pdf: PdfOptions::default(),
+            coloring_book: ColoringBook::default(),
+            margin: DEFAULT_MARGIN,
+            raster: RasterOptions::default(),
+            background: Background::Transparent,
+            fonts: Fonts::default(),
+            caption_options: CaptionOptions::default(),
             export: ExportState::Idle,
+            export_dir: load_export_dir(),
         }
     }
 }
@@ -0,0 +1,114 @@
+use std::fmt;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum NormalizationForm {
+    /// The text is used exactly as typed.
+    None,
+    /// Canonical composition: `е` followed by a combining diaeresis becomes `ё`.
+    #[default]
+    Nfc,
+    /// Compatibility composition, additionally folding ligatures (`ﬁ`) and
+    /// width variants.
+    Nfkc,
+}
+
+impl NormalizationForm {
+    pub const ALL: [NormalizationForm; 3] = [
+        NormalizationForm::None,
+        NormalizationForm::Nfc,
+        NormalizationForm::Nfkc,
+    ];
+}
+
+impl fmt::Display for NormalizationForm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NormalizationForm::None => "Без нормализации",
+            NormalizationForm::Nfc => "NFC",
+            NormalizationForm::Nfkc => "NFKC",
+        })
+    }
+}
+
+/// Rewrites applied to the text before its letters are looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Normalization {
+    pub form: NormalizationForm,
+    /// Read `ё` as `е`.
+    pub yo_as_ye: bool,
+    /// Reduce letters missing from the active alphabets to their base
+    /// letters through NFKD, e.g. `é` to `e`. Letters the alphabets know,
+    /// such as `й`, are kept.
+    pub strip_diacritics: bool,
+    /// Read `ß` as `ss`.
+    pub fold_sharp_s: bool,
+}
+
+/// A character sequence replaced during normalisation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Substitution {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Normalized {
+    pub text: String,
+    /// Replacements made by the letter folding options, in text order.
+    pub substitutions: Vec<Substitution>,
+}
+
+/// Applies `normalization` to `text`. `is_known` tells whether a character
+/// already has a value and must be left alone by diacritic stripping.
+pub fn normalize(
+    text: &str,
+    normalization: &Normalization,
+    is_known: impl Fn(char) -> bool,
+) -> Normalized {
+    let composed: String = match normalization.form {
+        NormalizationForm::None => text.to_string(),
+        NormalizationForm::Nfc => text.nfc().collect(),
+        NormalizationForm::Nfkc => text.nfkc().collect(),
+    };
+
+    let mut normalized = Normalized::default();
+
+    for symbol in composed.chars() {
+        let replacement = match symbol {
+            'ё' if normalization.yo_as_ye => Some("е".to_string()),
+            'Ё' if normalization.yo_as_ye => Some("Е".to_string()),
+            'ß' if normalization.fold_sharp_s => Some("ss".to_string()),
+            'ẞ' if normalization.fold_sharp_s => Some("SS".to_string()),
+            _ if normalization.strip_diacritics && !is_known(symbol) => {
+                let base = symbol
+                    .to_string()
+                    .nfkd()
+                    .filter(|&c| !is_combining_mark(c))
+                    .collect::<String>();
+
+                (!base.is_empty() && base != symbol.to_string()).then_some(base)
+            }
+            _ => None,
+        };
+
+        match replacement {
+            Some(to) => {
+                normalized.text.push_str(&to);
+                normalized.substitutions.push(Substitution {
+                    from: symbol.to_string(),
+                    to,
+                });
+            }
+            None => normalized.text.push(symbol),
+        }
+    }
+
+    normalized
+}
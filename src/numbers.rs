@@ -0,0 +1,118 @@
+use std::fmt;
+
+/// How numbers in the input contribute to the seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum NumberMode {
+    /// Numbers are skipped like any other unknown character.
+    #[default]
+    Ignore,
+    /// Every ASCII digit adds its own value. A zero counts as the modulus,
+    /// as in a date of birth.
+    Digits,
+    /// As `Digits`, and words written entirely in uppercase Roman numerals
+    /// (`XIV`) add the value of the whole number.
+    DigitsAndRoman,
+}
+
+impl NumberMode {
+    pub const ALL: [NumberMode; 3] = [
+        NumberMode::Ignore,
+        NumberMode::Digits,
+        NumberMode::DigitsAndRoman,
+    ];
+
+    pub fn digits(&self) -> bool {
+        *self != NumberMode::Ignore
+    }
+
+    pub fn roman(&self) -> bool {
+        *self == NumberMode::DigitsAndRoman
+    }
+}
+
+impl fmt::Display for NumberMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            NumberMode::Ignore => "Не учитывать",
+            NumberMode::Digits => "Цифры",
+            NumberMode::DigitsAndRoman => "Цифры и римские числа",
+        };
+
+        f.write_str(name)
+    }
+}
+
+const ROMAN: [(&str, u32); 13] = [
+    ("M", 1000),
+    ("CM", 900),
+    ("D", 500),
+    ("CD", 400),
+    ("C", 100),
+    ("XC", 90),
+    ("L", 50),
+    ("XL", 40),
+    ("X", 10),
+    ("IX", 9),
+    ("V", 5),
+    ("IV", 4),
+    ("I", 1),
+];
+
+/// Value of a canonically written uppercase Roman numeral.
+pub fn parse_roman(word: &str) -> Option<u32> {
+    let mut rest = word;
+    let mut value = 0;
+
+    for (symbol, amount) in ROMAN {
+        while let Some(tail) = rest.strip_prefix(symbol) {
+            rest = tail;
+            value += amount;
+        }
+    }
+
+    // Round-tripping rejects non canonical spellings such as `IIII` or `VX`
+    (rest.is_empty() && value > 0 && to_roman(value) == word).then_some(value)
+}
+
+fn to_roman(mut value: u32) -> String {
+    let mut roman = String::new();
+
+    for (symbol, amount) in ROMAN {
+        while value >= amount {
+            roman.push_str(symbol);
+            value -= amount;
+        }
+    }
+
+    roman
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_canonical_numerals() {
+        assert_eq!(parse_roman("I"), Some(1));
+        assert_eq!(parse_roman("XIV"), Some(14));
+        assert_eq!(parse_roman("XC"), Some(90));
+        assert_eq!(parse_roman("MCMXC"), Some(1990));
+        assert_eq!(parse_roman("MMXXIV"), Some(2024));
+    }
+
+    #[test]
+    fn rejects_non_canonical_numerals() {
+        for word in ["IIII", "VX", "IC", "IL", "VV", "XXXX", "IIV", "MIM"] {
+            assert_eq!(parse_roman(word), None, "{word}");
+        }
+    }
+
+    #[test]
+    fn rejects_other_words() {
+        for word in ["", "xiv", "Xiv", "MIX2", "Мир"] {
+            assert_eq!(parse_roman(word), None, "{word}");
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt;
+
+// Chaldean values of the Latin letters
+const CHALDEAN: [(char, u16); 26] = [
+    ('a', 1),
+    ('b', 2),
+    ('c', 3),
+    ('d', 4),
+    ('e', 5),
+    ('f', 8),
+    ('g', 3),
+    ('h', 5),
+    ('i', 1),
+    ('j', 1),
+    ('k', 2),
+    ('l', 3),
+    ('m', 4),
+    ('n', 5),
+    ('o', 7),
+    ('p', 8),
+    ('q', 1),
+    ('r', 2),
+    ('s', 3),
+    ('t', 4),
+    ('u', 6),
+    ('v', 6),
+    ('w', 6),
+    ('x', 5),
+    ('y', 1),
+    ('z', 7),
+];
+
+// Standard Hebrew gematria, final forms share the value of the letter
+const GEMATRIA: [(char, u16); 27] = [
+    ('א', 1),
+    ('ב', 2),
+    ('ג', 3),
+    ('ד', 4),
+    ('ה', 5),
+    ('ו', 6),
+    ('ז', 7),
+    ('ח', 8),
+    ('ט', 9),
+    ('י', 10),
+    ('כ', 20),
+    ('ך', 20),
+    ('ל', 30),
+    ('מ', 40),
+    ('ם', 40),
+    ('נ', 50),
+    ('ן', 50),
+    ('ס', 60),
+    ('ע', 70),
+    ('פ', 80),
+    ('ף', 80),
+    ('צ', 90),
+    ('ץ', 90),
+    ('ק', 100),
+    ('ר', 200),
+    ('ש', 300),
+    ('ת', 400),
+];
+
+// Church Slavonic numeric values of the Cyrillic letters. Letters that are
+// no numerals are zero, the modern ones follow the letters they come from.
+const CHURCH: [(char, u16); 45] = [
+    ('а', 1),
+    ('б', 0),
+    ('в', 2),
+    ('г', 3),
+    ('д', 4),
+    ('е', 5),
+    ('ё', 5),
+    ('є', 5),
+    ('э', 5),
+    ('ж', 0),
+    ('ѕ', 6),
+    ('з', 7),
+    ('и', 8),
+    ('й', 8),
+    ('ѳ', 9),
+    ('і', 10),
+    ('ї', 10),
+    ('к', 20),
+    ('л', 30),
+    ('м', 40),
+    ('н', 50),
+    ('ѯ', 60),
+    ('о', 70),
+    ('п', 80),
+    ('ч', 90),
+    ('р', 100),
+    ('с', 200),
+    ('т', 300),
+    ('у', 400),
+    ('ў', 400),
+    ('ѵ', 400),
+    ('ф', 500),
+    ('х', 600),
+    ('ѱ', 700),
+    ('ѡ', 800),
+    ('ц', 900),
+    ('ш', 0),
+    ('щ', 0),
+    ('ъ', 0),
+    ('ы', 0),
+    ('ь', 0),
+    ('ѣ', 0),
+    ('ю', 0),
+    ('я', 0),
+    ('ѧ', 0),
+];
+
+/// The rule assigning numbers to letters.
+///
+/// Systems other than the Pythagorean one have a table of values for their
+/// own script. Letters missing from it, such as those of other scripts or
+/// the Latin letters with diacritics under the Chaldean system, keep their
+/// Pythagorean value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum NumerologySystem {
+    /// Letters are numbered 1..=modulus in alphabet order, cycling.
+    #[default]
+    Pythagorean,
+    /// Traditional Chaldean values for the Latin letters.
+    Chaldean,
+    /// Hebrew gematria reduced into the digit range.
+    Gematria,
+    /// Church Slavonic numerals reduced into the digit range. Modern
+    /// letters take the value of the letter they come from (ё, э and й those
+    /// of е and и), letters that are no numerals (б, ж, ш, щ, ъ, ы, ь, ѣ, ю,
+    /// я) count as zero, that is as the modulus.
+    Church,
+}
+
+impl NumerologySystem {
+    pub const ALL: [NumerologySystem; 4] = [
+        NumerologySystem::Pythagorean,
+        NumerologySystem::Chaldean,
+        NumerologySystem::Gematria,
+        NumerologySystem::Church,
+    ];
+
+    fn table(&self) -> &'static [(char, u16)] {
+        match self {
+            NumerologySystem::Pythagorean => &[],
+            NumerologySystem::Chaldean => &CHALDEAN,
+            NumerologySystem::Gematria => &GEMATRIA,
+            NumerologySystem::Church => &CHURCH,
+        }
+    }
+
+    /// Value in `1..=modulus` of a lowercase `letter` found at zero based
+    /// `position` of its alphabet.
+    pub fn value(&self, letter: char, position: usize, modulus: u16) -> u16 {
+        self.table()
+            .iter()
+            .find(|(symbol, _)| *symbol == letter)
+            .map(|(_, value)| digit_value(*value as u32, modulus))
+            .unwrap_or((position % modulus as usize) as u16 + 1)
+    }
+}
+
+impl fmt::Display for NumerologySystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            NumerologySystem::Pythagorean => "Пифагорейская",
+            NumerologySystem::Chaldean => "Халдейская",
+            NumerologySystem::Gematria => "Гематрия",
+            NumerologySystem::Church => "Церковнославянская",
+        };
+
+        f.write_str(name)
+    }
+}
+
+// Digital root generalised to any modulus, keeping the result in 1..=modulus
+pub(crate) fn reduce(value: u32, modulus: u16) -> u16 {
+    ((value - 1) % modulus as u32 + 1) as u16
+}
+
+// Value of a written digit or numeral. Sums are taken modulo the modulus, so
+// a zero counts as the modulus itself.
+pub(crate) fn digit_value(digit: u32, modulus: u16) -> u16 {
+    if digit == 0 {
+        modulus
+    } else {
+        reduce(digit, modulus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_chaldean_letters() {
+        let system = NumerologySystem::Chaldean;
+
+        assert_eq!(system.value('a', 0, 9), 1);
+        assert_eq!(system.value('f', 5, 9), 8);
+        assert_eq!(system.value('o', 14, 9), 7);
+        assert_eq!(system.value('z', 25, 9), 7);
+        assert_eq!(system.value('f', 5, 7), 1);
+        // Not in the table: the Pythagorean value of its position
+        assert_eq!(system.value('ä', 26, 9), 9);
+    }
+
+    #[test]
+    fn values_church_letters() {
+        let system = NumerologySystem::Church;
+
+        assert_eq!(system.value('а', 0, 9), 1);
+        assert_eq!(system.value('к', 11, 9), 2);
+        assert_eq!(system.value('ц', 22, 9), 9);
+        assert_eq!(system.value('ѳ', 37, 9), 9);
+        assert_eq!(system.value('ѱ', 36, 9), 7);
+        assert_eq!(system.value('ё', 6, 9), system.value('е', 5, 9));
+        assert_eq!(system.value('й', 10, 9), system.value('и', 9, 9));
+
+        for letter in ['б', 'ж', 'ш', 'щ', 'ъ', 'ы', 'ь', 'ю', 'я'] {
+            assert_eq!(system.value(letter, 0, 9), 9, "{letter}");
+            assert_eq!(system.value(letter, 0, 12), 12, "{letter}");
+        }
+
+        // Letters of other scripts keep their Pythagorean value
+        assert_eq!(system.value('b', 1, 9), 2);
+    }
+
+    #[test]
+    fn values_hebrew_letters() {
+        let system = NumerologySystem::Gematria;
+
+        assert_eq!(system.value('א', 0, 9), 1);
+        assert_eq!(system.value('ך', 10, 9), system.value('כ', 10, 9));
+        assert_eq!(system.value('ת', 21, 9), 4);
+    }
+}
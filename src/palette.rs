@@ -0,0 +1,148 @@
+use crate::calculation::{MAX_MODULUS, MIN_MODULUS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn from_hex(hex: u32) -> Self {
+        Self {
+            r: (hex >> 16) as u8,
+            g: (hex >> 8) as u8,
+            b: hex as u8,
+        }
+    }
+
+    // `hue` in degrees, saturation and value in 0.0..=1.0
+    fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let chroma = value * saturation;
+        let sector = (hue / 60.0) % 6.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = value - chroma;
+        let channel = |c: f32| ((c + m) * 255.0).round() as u8;
+
+        Self {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+        }
+    }
+}
+
+const NINE: [Rgb; 9] = [
+    Rgb::from_hex(0xE6194B),
+    Rgb::from_hex(0xF58231),
+    Rgb::from_hex(0xFFE119),
+    Rgb::from_hex(0xBFEF45),
+    Rgb::from_hex(0x3CB44B),
+    Rgb::from_hex(0x42D4F4),
+    Rgb::from_hex(0x4363D8),
+    Rgb::from_hex(0x911EB4),
+    Rgb::from_hex(0xF032E6),
+];
+
+const RAINBOW: [Rgb; 7] = [
+    Rgb::from_hex(0xE53935),
+    Rgb::from_hex(0xFB8C00),
+    Rgb::from_hex(0xFDD835),
+    Rgb::from_hex(0x43A047),
+    Rgb::from_hex(0x29B6F6),
+    Rgb::from_hex(0x1E88E5),
+    Rgb::from_hex(0x8E24AA),
+];
+
+// Aries to Pisces, following the traditional element colours
+const ZODIAC: [Rgb; 12] = [
+    Rgb::from_hex(0xD32F2F),
+    Rgb::from_hex(0x388E3C),
+    Rgb::from_hex(0xFBC02D),
+    Rgb::from_hex(0xB0BEC5),
+    Rgb::from_hex(0xFF8F00),
+    Rgb::from_hex(0x8D6E63),
+    Rgb::from_hex(0xF48FB1),
+    Rgb::from_hex(0x6A1B9A),
+    Rgb::from_hex(0x7E57C2),
+    Rgb::from_hex(0x455A64),
+    Rgb::from_hex(0x00ACC1),
+    Rgb::from_hex(0x26A69A),
+];
+
+/// One colour per digit of the reduction range `1..=modulus`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<Rgb>,
+}
+
+impl Palette {
+    /// Hand picked palettes for 7, 9 and 12, evenly spaced hues otherwise.
+    /// Moduli outside [`MIN_MODULUS`]`..=`[`MAX_MODULUS`] get the colours of
+    /// the nearest bound.
+    pub fn for_modulus(modulus: u16) -> Self {
+        let modulus = modulus.clamp(MIN_MODULUS, MAX_MODULUS);
+        let colors = match modulus {
+            7 => RAINBOW.to_vec(),
+            9 => NINE.to_vec(),
+            12 => ZODIAC.to_vec(),
+            _ => (0..modulus)
+                .map(|index| Rgb::from_hsv(index as f32 * 360.0 / modulus as f32, 0.75, 0.95))
+                .collect(),
+        };
+
+        Self { colors }
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Colour of a digit in `1..=modulus`.
+    pub fn color(&self, value: u16) -> Rgb {
+        self.colors[(value as usize - 1) % self.colors.len()]
+    }
+
+    pub fn colors(&self) -> &[Rgb] {
+        &self.colors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn has_a_colour_per_digit() {
+        assert_eq!(Palette::for_modulus(7).colors(), RAINBOW);
+        assert_eq!(Palette::for_modulus(9).colors(), NINE);
+        assert_eq!(Palette::for_modulus(12).colors(), ZODIAC);
+        assert_eq!(Palette::for_modulus(5).len(), 5);
+    }
+
+    #[test]
+    fn clamps_the_modulus() {
+        let smallest = Palette::for_modulus(0);
+
+        assert_eq!(smallest, Palette::for_modulus(MIN_MODULUS));
+        assert_eq!(smallest.len(), usize::from(MIN_MODULUS));
+        assert_eq!(smallest.color(1), smallest.colors()[0]);
+
+        let largest = Palette::for_modulus(MAX_MODULUS + 51);
+
+        assert_eq!(largest, Palette::for_modulus(MAX_MODULUS));
+        assert_eq!(largest.len(), usize::from(MAX_MODULUS));
+    }
+}
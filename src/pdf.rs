@@ -1,185 +1,1476 @@
-use std::path::PathBuf;
+//! PDF rendering of a mandala on A3 to A5, Letter, square or custom pages.
 
-use glam::{Mat2, Vec2};
+use std::fmt;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use glam::Vec2;
 use printpdf::*;
+use ttf_parser::Face;
+
+use crate::caption::{CaptionBlock, CaptionOptions, CaptionPosition};
+use crate::error::{Error, Result};
+use crate::fonts::{FontSource, Fonts, LoadedFont};
+use crate::grid::MandalaGrid;
+use crate::interpretation::meaning;
+use crate::layout::Sizes;
+use crate::numerology::NumerologySystem;
+use crate::palette::Palette;
+use crate::text::{TextLine, layout_text};
+use crate::trace::Trace;
+
+/// Blank space along the page edges used by default, in millimetres.
+pub const DEFAULT_MARGIN: f32 = 10.0;
+
+// The smallest mandala worth printing, in millimetres
+const MIN_SKETCH_WIDTH: f32 = 30.0;
+// Room kept under the mandala for the footer, and between the caption and
+// its neighbours
+const FOOTER_HEIGHT: f32 = 8.0;
+const CAPTION_GAP: f32 = 5.0;
+// A colouring book also keeps a row for the legend above the footer
+const LEGEND_HEIGHT: f32 = 12.0;
+const SWATCH_SIZE: f32 = 6.0;
+// Space between the heading and the triangle of a report, and the size of
+// the digits relative to the cells of the triangle
+const TRIANGLE_GAP: f32 = 10.0;
+const TRIANGLE_DIGIT_RATIO: f32 = 0.45;
+// Line spacing of the report text relative to the font size
+const TEXT_LINE_HEIGHT: f32 = 1.2;
+
+const LINE_WIDTH: f32 = 0.5;
+/// Line width of a colouring book, in points. Bold enough to stop a
+/// colouring pencil.
+pub const COLORING_BOOK_LINE_WIDTH: f32 = 1.2;
+// Grey of the digits in a colouring book, light enough to colour over
+const HINT_GRAY: f32 = 0.55;
+// Size of the digits and the dots in a colouring book, relative to the
+// digits of a finished mandala
+const HINT_SCALE: f32 = 0.7;
+const DOT_RATIO: f32 = 0.05;
+
+// Digits are set at 12pt in the cells of a 16 row mandala 210mm wide and
+// scale with the cell size
+const DIGIT_FONT_RATIO: f32 = 12.0 * 33.0 / 210.0;
+
+// Share of the palette colour in a tinted cell, the rest is white
+const TINT: f32 = 0.3;
+
+// Resource names of the embedded fonts, fixed so reproducible documents do
+// not depend on the random ones printpdf picks
+const TEXT_FONT_ID: &str = "Roboto";
+const DIGITS_FONT_ID: &str = "Digits";
+const CAPTION_FONT_ID: &str = "Caption";
+
+/// How the cells of the mandala are painted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CellColoring {
+    /// The palette colour of the digit, as on screen.
+    #[default]
+    Full,
+    /// A pale shade of the palette colour, cheap to print and easy to read.
+    Tinted,
+    /// Black lines and digits only.
+    Plain,
+}
+
+impl CellColoring {
+    pub const ALL: [CellColoring; 3] = [
+        CellColoring::Full,
+        CellColoring::Tinted,
+        CellColoring::Plain,
+    ];
+}
+
+impl fmt::Display for CellColoring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CellColoring::Full => "Цветные ячейки",
+            CellColoring::Tinted => "Светлые ячейки",
+            CellColoring::Plain => "Без цвета",
+        })
+    }
+}
+
+/// Paper size, in portrait orientation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PageSize {
+    A3,
+    #[default]
+    A4,
+    A5,
+    /// US Letter, 8.5 by 11 inches.
+    Letter,
+    /// A square as wide as A4.
+    Square,
+    /// Any size, in millimetres.
+    Custom {
+        width: f32,
+        height: f32,
+    },
+}
 
-static ROBOTO_FONT: &[u8] = include_bytes!("./Roboto-Light.ttf");
+impl PageSize {
+    /// Sizes offered by the application.
+    pub const PRESETS: [PageSize; 5] = [
+        PageSize::A3,
+        PageSize::A4,
+        PageSize::A5,
+        PageSize::Letter,
+        PageSize::Square,
+    ];
 
-const PAGE_WIDTH: f32 = 210.0;
-const PAGE_HEIGHT: f32 = 297.0;
-const SKETCH_OFFSET: f32 = 10.0;
+    /// Width and height in millimetres.
+    pub fn dimensions(&self) -> (f32, f32) {
+        match *self {
+            PageSize::A3 => (297.0, 420.0),
+            PageSize::A4 => (210.0, 297.0),
+            PageSize::A5 => (148.0, 210.0),
+            PageSize::Letter => (215.9, 279.4),
+            PageSize::Square => (210.0, 210.0),
+            PageSize::Custom { width, height } => (width, height),
+        }
+    }
+}
 
-static DIGITS: [&str; 9] = ["1", "2", "3", "4", "5", "6", "7", "8", "9"];
+impl fmt::Display for PageSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PageSize::A3 => f.write_str("A3"),
+            PageSize::A4 => f.write_str("A4"),
+            PageSize::A5 => f.write_str("A5"),
+            PageSize::Letter => f.write_str("Letter"),
+            PageSize::Square => f.write_str("Квадрат 210×210 мм"),
+            PageSize::Custom { width, height } => write!(f, "{}×{} мм", width, height),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 2] = [Orientation::Portrait, Orientation::Landscape];
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Orientation::Portrait => "Книжная",
+            Orientation::Landscape => "Альбомная",
+        })
+    }
+}
+
+/// What a colouring book shows inside the cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum CellMarks {
+    /// The digit, matching a colour of the legend.
+    #[default]
+    Digits,
+    /// A small dot, to tell the cells apart without any hint.
+    Dots,
+    /// Empty cells for free colouring.
+    Blank,
+}
+
+impl CellMarks {
+    pub const ALL: [CellMarks; 3] = [CellMarks::Digits, CellMarks::Dots, CellMarks::Blank];
+}
+
+impl fmt::Display for CellMarks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CellMarks::Digits => "Цифры в ячейках",
+            CellMarks::Dots => "Точки в ячейках",
+            CellMarks::Blank => "Пустые ячейки",
+        })
+    }
+}
+
+/// An uncoloured mandala to colour by hand, with a legend of suggested
+/// colours under it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColoringBook {
+    pub marks: CellMarks,
+    /// Width of the grid lines, in points.
+    pub line_width: f32,
+}
 
-struct Sizes {
-    segment_size: f32,
-    half_segment_size: f32,
-    line_offset: f32,
-    half_line_offset: f32,
-    quarter_segment_size: f32,
-    translation_vector: Vec2,
+impl ColoringBook {
+    /// Fails when the line width is not a positive number.
+    pub fn check(&self) -> Result<()> {
+        check_line_width(self.line_width).map(|_| ())
+    }
 }
 
+impl Default for ColoringBook {
+    fn default() -> Self {
+        Self {
+            marks: CellMarks::default(),
+            line_width: COLORING_BOOK_LINE_WIDTH,
+        }
+    }
+}
+
+/// `width` back when it is a usable line width, finite and above zero.
+pub fn check_line_width(width: f32) -> Result<f32> {
+    if width.is_finite() && width > 0.0 {
+        Ok(width)
+    } else {
+        Err(Error::InvalidLineWidth { width })
+    }
+}
+
+/// How the mandala pages look.
+#[derive(Debug, Clone)]
+pub struct PdfOptions {
+    /// Ignored by a colouring book, which always leaves the cells blank.
+    pub coloring: CellColoring,
+    /// Turns the mandala pages into colouring pages.
+    pub coloring_book: Option<ColoringBook>,
+    pub page_size: PageSize,
+    pub orientation: Orientation,
+    /// Blank space along every edge, in millimetres.
+    pub margin: f32,
+    pub fonts: Fonts,
+    pub caption: CaptionOptions,
+    /// Stored in the document properties when not empty.
+    pub author: String,
+    /// Fixes the creation date and the document identifiers, so the same
+    /// mandalas and options always give a byte-identical file.
+    pub reproducible: bool,
+    /// Turns every mandala page into the cover of a report, followed by the
+    /// triangle, the calculation when there is one and the digit
+    /// statistics.
+    pub report: bool,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            coloring: CellColoring::default(),
+            coloring_book: None,
+            page_size: PageSize::default(),
+            orientation: Orientation::default(),
+            margin: DEFAULT_MARGIN,
+            fonts: Fonts::default(),
+            caption: CaptionOptions::default(),
+            author: String::new(),
+            reproducible: false,
+            report: false,
+        }
+    }
+}
+
+impl PdfOptions {
+    fn page(&self) -> Page {
+        let (short, long) = self.page_size.dimensions();
+        let (width, height) = match self.orientation {
+            Orientation::Portrait => (short.min(long), short.max(long)),
+            Orientation::Landscape => (short.max(long), short.min(long)),
+        };
+
+        Page {
+            width,
+            height,
+            margin: self.margin,
+            legend: self.coloring_book.is_some(),
+            caption: 0.0,
+            caption_above: self.caption.position == CaptionPosition::Above,
+        }
+    }
+}
+
+// The page every part of the document is laid out on, in millimetres
+#[derive(Debug, Clone, Copy)]
+struct Page {
+    width: f32,
+    height: f32,
+    margin: f32,
+    /// Keeps room for the legend of a colouring book.
+    legend: bool,
+    /// Room kept for the caption, with the gaps around it.
+    caption: f32,
+    caption_above: bool,
+}
+
+impl Page {
+    fn text_width(&self) -> f32 {
+        self.width - self.margin * 2.0
+    }
+
+    fn top(&self) -> f32 {
+        self.height - self.margin
+    }
+
+    // The mandala fills the space left by the caption, the legend and the
+    // footer
+    fn sketch_width(&self) -> f32 {
+        let legend = if self.legend { LEGEND_HEIGHT } else { 0.0 };
+
+        self.text_width()
+            .min(self.height - self.margin * 2.0 - self.caption - legend - FOOTER_HEIGHT)
+    }
+
+    fn sketch_top(&self) -> f32 {
+        if self.caption_above {
+            self.top() - self.caption
+        } else {
+            self.top()
+        }
+    }
+
+    fn sketch_center(&self) -> Vec2 {
+        Vec2::new(
+            self.width / 2.0,
+            self.sketch_top() - self.sketch_width() / 2.0,
+        )
+    }
+
+    fn caption_top(&self) -> f32 {
+        if self.caption_above {
+            self.top()
+        } else {
+            self.top() - self.sketch_width() - CAPTION_GAP
+        }
+    }
+
+    fn check(&self) -> Result<()> {
+        let valid = self.width.is_finite()
+            && self.height.is_finite()
+            && self.margin >= 0.0
+            && self.sketch_width() >= MIN_SKETCH_WIDTH;
+
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidPage {
+                width: self.width,
+                height: self.height,
+                margin: self.margin,
+            })
+        }
+    }
+
+    fn new_page(&self, contents: Vec<Op>) -> PdfPage {
+        PdfPage::new(Mm(self.width), Mm(self.height), contents)
+    }
+}
+
+/// A mandala placed on its own page of a document.
+pub struct PdfMandala<'a> {
+    pub grid: &'a MandalaGrid,
+    /// Text the mandala was calculated from, kept in the document keywords.
+    pub input: &'a str,
+    /// Printed with the mandala, as the caption options say.
+    pub caption: &'a str,
+    /// Printed in the footer when given.
+    pub system: Option<NumerologySystem>,
+    /// Adds the calculation pages after the mandala.
+    pub trace: Option<&'a Trace>,
+}
+
+/// A row of a batch no mandala could be built for.
+#[derive(Debug, Clone)]
+pub struct RejectedRow {
+    /// One based line of the source file.
+    pub line: u64,
+    pub text: String,
+    pub reason: String,
+}
+
+/// Saves the mandala into `path`, returning it back once written.
 pub async fn save_mandala_pdf(
-    calculation: Vec<Vec<u16>>,
+    path: PathBuf,
+    calculation: MandalaGrid,
     input: String,
-) -> Result<PathBuf, String> {
-    let mut document = PdfDocument::new("Mandala");
-
-    let segment_size = PAGE_WIDTH / 33.0;
-    let half_segment_size = segment_size / 2.0;
-    let line_offset = ((half_segment_size * 2.0).powf(2.0) - half_segment_size.powf(2.0)).sqrt();
-
-    let sizes = Sizes {
-        segment_size: segment_size,
-        half_segment_size: segment_size / 2.0,
-        line_offset: ((half_segment_size * 2.0).powf(2.0) - half_segment_size.powf(2.0)).sqrt(),
-        half_line_offset: line_offset / 2.0,
-        quarter_segment_size: half_segment_size / 2.0,
-        translation_vector: Vec2::new(
-            PAGE_WIDTH / 2.0,
-            PAGE_HEIGHT - (PAGE_WIDTH / 2.0) - SKETCH_OFFSET,
+    system: Option<NumerologySystem>,
+    trace: Option<Trace>,
+    options: PdfOptions,
+) -> Result<PathBuf> {
+    write_mandala_pdf(
+        &path,
+        &calculation,
+        &input,
+        system,
+        trace.as_ref(),
+        &options,
+    )?;
+
+    Ok(path)
+}
+
+pub fn write_mandala_pdf(
+    path: &Path,
+    calculation: &MandalaGrid,
+    input: &str,
+    system: Option<NumerologySystem>,
+    trace: Option<&Trace>,
+    options: &PdfOptions,
+) -> Result<()> {
+    let pdf_bytes = render_mandala_pdf(calculation, input, system, trace, options)?;
+
+    std::fs::write(path, pdf_bytes).map_err(|source| Error::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// The mandala page with `input` as the caption, followed by the
+/// calculation pages when `trace` is given.
+pub fn render_mandala_pdf(
+    calculation: &MandalaGrid,
+    input: &str,
+    system: Option<NumerologySystem>,
+    trace: Option<&Trace>,
+    options: &PdfOptions,
+) -> Result<Vec<u8>> {
+    render_mandalas_pdf(
+        &[PdfMandala {
+            grid: calculation,
+            input,
+            caption: input,
+            system,
+            trace,
+        }],
+        &[],
+        options,
+    )
+}
+
+/// One page per mandala, each followed by its calculation pages, and a
+/// closing list of the `rejected` rows when there are any.
+pub fn render_mandalas_pdf(
+    mandalas: &[PdfMandala],
+    rejected: &[RejectedRow],
+    options: &PdfOptions,
+) -> Result<Vec<u8>> {
+    let mut page = options.page();
+
+    page.check()?;
+    options.caption.check()?;
+
+    if let Some(book) = &options.coloring_book {
+        book.check()?;
+    }
+
+    let fonts = DocumentFonts::load(options, mandalas, rejected)?;
+    let faces = fonts.caption_faces()?;
+    let captions = fonts.layout_captions(&faces, mandalas, &options.caption, &page);
+
+    // Every mandala of the document is as large as the longest caption
+    // lets it be
+    page.caption = captions
+        .iter()
+        .map(|block| block.height)
+        .filter(|&height| height > 0.0)
+        .map(|height| Mm::from(Pt(height)).0 + CAPTION_GAP * 2.0)
+        .fold(0.0, f32::max);
+    page.check()?;
+
+    let mut document = PdfDocument::new("");
+
+    document.metadata.info = document_info(mandalas, options);
+    fonts.register(&mut document);
+
+    let mut pages = Vec::new();
+
+    for (mandala, caption) in mandalas.iter().zip(&captions) {
+        pages.push(generate_mandala_page(
+            mandala, caption, options, &page, &fonts,
+        ));
+
+        if options.report {
+            pages.push(generate_triangle_page(mandala.grid, options, &page, &fonts));
+        }
+
+        if let Some(trace) = mandala.trace {
+            pages.extend(generate_trace_pages(
+                trace,
+                mandala.grid,
+                TextFlow::new(&page, &fonts, &faces),
+            ));
+        }
+
+        if options.report {
+            pages.extend(generate_statistics_pages(
+                mandala.grid,
+                TextFlow::new(&page, &fonts, &faces),
+            ));
+        }
+    }
+
+    if !rejected.is_empty() {
+        pages.extend(generate_rejected_pages(
+            rejected,
+            TextFlow::new(&page, &fonts, &faces),
+        ));
+    }
+
+    let mut bytes = document
+        .with_pages(pages)
+        .save(&PdfSaveOptions::default(), &mut Vec::new());
+
+    mark_hex_strings(&mut bytes);
+
+    if options.reproducible {
+        replace_document_ids(&mut bytes);
+    }
+
+    Ok(bytes)
+}
+
+// Title, subject, author and keywords shown in the document properties
+fn document_info(mandalas: &[PdfMandala], options: &PdfOptions) -> PdfDocumentInfo {
+    let date = if options.reproducible {
+        OffsetDateTime::epoch()
+    } else {
+        OffsetDateTime::now_utc()
+    };
+
+    let (title, subject) = match mandalas {
+        [mandala] => (
+            format!("Мандала «{}»", mandala.caption),
+            "Нумерологическая мандала".to_string(),
+        ),
+        _ => (
+            format!("Мандалы ({})", mandalas.len()),
+            "Нумерологические мандалы".to_string(),
         ),
     };
 
-    let roboto_font = ParsedFont::from_bytes(ROBOTO_FONT, 0, &mut Vec::new())
-        .ok_or("Failed to parse Roboto font")?;
-    let roboto_font_id = document.add_font(&roboto_font);
-
-    let mut contents = vec![
-        Op::SetFillColor {
-            col: printpdf::Color::Rgb(printpdf::Rgb {
-                r: 0.0,
-                g: 0.0,
-                b: 0.0,
-                icc_profile: None,
-            }),
-        },
+    let mut keywords: Vec<String> = Vec::new();
+
+    for mandala in mandalas {
+        let system = mandala.system.map(|system| system.to_string());
+        let seed = format_digits(mandala.grid.seed());
+
+        for keyword in [Some(mandala.input.to_string()), system, Some(seed)]
+            .into_iter()
+            .flatten()
+        {
+            if !keywords.contains(&keyword) {
+                keywords.push(keyword);
+            }
+        }
+    }
+
+    PdfDocumentInfo {
+        document_title: text_string(&title),
+        subject: text_string(&subject),
+        author: text_string(&options.author),
+        keywords: vec![text_string(&keywords.join(", "))],
+        creator: format!("Mandala {}", env!("CARGO_PKG_VERSION")),
+        producer: "printpdf".to_string(),
+        creation_date: date,
+        modification_date: date,
+        metadata_date: date,
+        ..PdfDocumentInfo::default()
+    }
+}
+
+// printpdf writes document properties as literal strings into a PDF 1.3
+// file, where text beyond Latin-1 has to be UTF-16BE with a byte order mark.
+// The encoded text is passed on in hex digits, which `mark_hex_strings` then
+// turns into hex strings.
+fn text_string(text: &str) -> String {
+    text.encode_utf16()
+        .fold("FEFF".to_string(), |mut hex, unit| {
+            let _ = write!(hex, "{:04X}", unit);
+            hex
+        })
+}
+
+// Swaps the parentheses around the `text_string` properties for angle
+// brackets, keeping the length of the file
+fn mark_hex_strings(bytes: &mut [u8]) {
+    for key in [&b"/Title("[..], b"/Subject(", b"/Author(", b"/Keywords("] {
+        let Some(start) = bytes.windows(key.len()).rposition(|window| window == key) else {
+            continue;
+        };
+        let open = start + key.len() - 1;
+        let Some(length) = bytes[open + 1..].iter().position(|&byte| byte == b')') else {
+            continue;
+        };
+        let text = &bytes[open + 1..open + 1 + length];
+
+        if text.starts_with(b"FEFF") && text.iter().all(u8::is_ascii_hexdigit) {
+            bytes[open] = b'<';
+            bytes[open + 1 + length] = b'>';
+        }
+    }
+}
+
+// printpdf picks random document identifiers. They are replaced with a hash
+// of everything before the trailer, keeping their length so the offsets of
+// the cross-reference table stay valid.
+fn replace_document_ids(bytes: &mut [u8]) {
+    const MARKER: &[u8] = b"/ID[(";
+    const ID_LENGTH: usize = 32;
+
+    let Some(start) = bytes
+        .windows(MARKER.len())
+        .rposition(|window| window == MARKER)
+    else {
+        return;
+    };
+
+    let first = start + MARKER.len();
+    let second = first + ID_LENGTH + 2;
+
+    if bytes.len() < second + ID_LENGTH + 1
+        || &bytes[first + ID_LENGTH..second] != b")("
+        || bytes[second + ID_LENGTH] != b')'
+    {
+        return;
+    }
+
+    let id = format!(
+        "{:016X}{:016X}",
+        fnv1a(&bytes[..start], 0xcbf2_9ce4_8422_2325),
+        fnv1a(&bytes[..start], 0x6c62_272e_07bb_0142),
+    );
+
+    bytes[first..first + ID_LENGTH].copy_from_slice(id.as_bytes());
+    bytes[second..second + ID_LENGTH].copy_from_slice(id.as_bytes());
+}
+
+fn fnv1a(bytes: &[u8], basis: u64) -> u64 {
+    bytes.iter().fold(basis, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// A font registered in the document under a fixed resource name
+#[derive(Clone)]
+struct EmbeddedFont {
+    parsed: ParsedFont,
+    id: FontId,
+}
+
+impl EmbeddedFont {
+    fn new(font: &LoadedFont, id: &str) -> Result<Self> {
+        let parsed = ParsedFont::from_bytes(&font.data, font.index as usize, &mut Vec::new())
+            .ok_or(Error::FontParse)?;
+
+        Ok(Self {
+            parsed,
+            id: FontId(id.to_string()),
+        })
+    }
+
+    fn shape_text(&self, text: &str, options: &TextShapingOptions) -> ShapedText {
+        self.parsed.shape_text(text, options, &self.id)
+    }
+}
+
+// Roboto for headings and notes, the font of the digits and the chain of
+// fonts of the caption, which also sets the text pages of the report
+struct DocumentFonts {
+    text: EmbeddedFont,
+    digits: EmbeddedFont,
+    caption: Vec<EmbeddedFont>,
+    // Measured when laying the captions out
+    caption_chain: Vec<LoadedFont>,
+}
+
+impl DocumentFonts {
+    // Fails when the digits font cannot draw a digit of `mandalas`, or no
+    // font a character of their captions
+    fn load(
+        options: &PdfOptions,
+        mandalas: &[PdfMandala],
+        rejected: &[RejectedRow],
+    ) -> Result<Self> {
+        let fonts = &options.fonts;
+        let text = EmbeddedFont::new(&FontSource::Roboto.load()?, TEXT_FONT_ID)?;
+        let digits_font = fonts.digits.load()?;
+
+        for mandala in mandalas {
+            // Every digit the cells and the legend may show
+            let digits = (1..=mandala.grid.modulus())
+                .map(|digit| digit.to_string())
+                .collect::<String>();
+
+            digits_font.check(&digits)?;
+        }
+
+        let digits = match fonts.digits {
+            FontSource::Roboto => text.clone(),
+            _ => EmbeddedFont::new(&digits_font, DIGITS_FONT_ID)?,
+        };
+        // Text of the calculation pages, blank where no font has a character
+        let reported = mandalas
+            .iter()
+            .filter_map(|mandala| mandala.trace)
+            .flat_map(|trace| {
+                trace
+                    .normalized
+                    .iter()
+                    .chain(
+                        trace
+                            .substitutions
+                            .iter()
+                            .flat_map(|substitution| [&substitution.from, &substitution.to]),
+                    )
+                    .chain(trace.letters.iter().map(|(letter, _)| letter))
+            })
+            .chain(rejected.iter().flat_map(|row| [&row.text, &row.reason]))
+            .map(String::as_str);
+        let caption_chain = fonts.caption_chain(
+            mandalas
+                .iter()
+                .flat_map(|mandala| options.caption.texts(mandala.caption)),
+            reported,
+        )?;
+        let caption = caption_chain
+            .iter()
+            .enumerate()
+            .map(|(index, font)| match font.source {
+                FontSource::Roboto => Ok(text.clone()),
+                _ => EmbeddedFont::new(font, &format!("{}{}", CAPTION_FONT_ID, index)),
+            })
+            .collect::<Result<Vec<EmbeddedFont>>>()?;
+
+        Ok(Self {
+            text,
+            digits,
+            caption,
+            caption_chain,
+        })
+    }
+
+    fn caption_faces(&self) -> Result<Vec<Face<'_>>> {
+        self.caption_chain.iter().map(LoadedFont::face).collect()
+    }
+
+    // The caption block of every mandala, in points
+    fn layout_captions(
+        &self,
+        faces: &[Face],
+        mandalas: &[PdfMandala],
+        options: &CaptionOptions,
+        page: &Page,
+    ) -> Vec<CaptionBlock> {
+        let max_width = Mm(page.text_width()).into_pt().0;
+
+        mandalas
+            .iter()
+            .map(|mandala| CaptionBlock::new(faces, mandala.caption, options, max_width))
+            .collect()
+    }
+
+    fn register(&self, document: &mut PdfDocument) {
+        for font in [&self.text, &self.digits].into_iter().chain(&self.caption) {
+            document
+                .resources
+                .fonts
+                .map
+                .insert(font.id.clone(), font.parsed.clone());
+        }
+    }
+}
+
+fn generate_mandala_page(
+    mandala: &PdfMandala,
+    caption: &CaptionBlock,
+    options: &PdfOptions,
+    page: &Page,
+    fonts: &DocumentFonts,
+) -> PdfPage {
+    let sizes = Sizes::new(
+        mandala.grid.size(),
+        page.sketch_width(),
+        page.sketch_center(),
+    );
+
+    let mut contents = Vec::new();
+    let digit_size = sizes.segment_size * DIGIT_FONT_RATIO;
+
+    match options.coloring_book {
+        Some(book) => {
+            contents.extend(line_style(book.line_width));
+            generate_lines(&mut contents, &sizes);
+
+            contents.push(fill_gray(HINT_GRAY));
+            match book.marks {
+                CellMarks::Digits => generate_digits(
+                    mandala.grid,
+                    digit_size * HINT_SCALE,
+                    &fonts.digits,
+                    &mut contents,
+                    &sizes,
+                ),
+                CellMarks::Dots => generate_dots(mandala.grid, &mut contents, &sizes),
+                CellMarks::Blank => {}
+            }
+
+            contents.extend(line_style(LINE_WIDTH));
+            generate_legend(mandala.grid, page, &fonts.digits, &mut contents);
+        }
+        None => {
+            generate_cells(mandala.grid, options.coloring, &mut contents, &sizes);
+
+            contents.extend(line_style(LINE_WIDTH));
+            generate_lines(&mut contents, &sizes);
+            generate_digits(
+                mandala.grid,
+                digit_size,
+                &fonts.digits,
+                &mut contents,
+                &sizes,
+            );
+        }
+    }
+
+    generate_caption(caption, page, fonts, &mut contents);
+    if let Some(system) = mandala.system {
+        generate_footer(system, page, &fonts.text, &mut contents);
+    }
+
+    page.new_page(contents)
+}
+
+// Black outlines and fills, and lines `width` points wide
+fn line_style(width: f32) -> [Op; 3] {
+    [
+        fill_gray(0.0),
         Op::SetOutlineColor {
             col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
         },
-        Op::SetOutlineThickness { pt: Pt(0.5) },
-    ];
+        Op::SetOutlineThickness { pt: Pt(width) },
+    ]
+}
+
+fn fill_gray(level: f32) -> Op {
+    Op::SetFillColor {
+        col: Color::Rgb(Rgb::new(level, level, level, None)),
+    }
+}
+
+fn fill_polygon(points: impl IntoIterator<Item = Vec2>, mode: PaintMode) -> Op {
+    Op::DrawPolygon {
+        polygon: Polygon {
+            rings: vec![PolygonRing {
+                points: points
+                    .into_iter()
+                    .map(|point| LinePoint {
+                        p: Point::new(Mm(point.x), Mm(point.y)),
+                        bezier: false,
+                    })
+                    .collect(),
+            }],
+            mode,
+            winding_order: WindingOrder::NonZero,
+        },
+    }
+}
+
+// The caption block centred above or under the mandala, every run of it
+// set in the first font of the chain that can draw it
+fn generate_caption(
+    caption: &CaptionBlock,
+    page: &Page,
+    fonts: &DocumentFonts,
+    contents: &mut Vec<Op>,
+) {
+    let center = Mm(page.margin + page.text_width() / 2.0).into_pt().0;
+    let top = Mm(page.caption_top()).into_pt().0;
+
+    for line in &caption.lines {
+        let left = center - line.text.width / 2.0;
+
+        generate_text_line(
+            &line.text,
+            Point {
+                x: Pt(left),
+                y: Pt(top - line.baseline),
+            },
+            line.font_size,
+            fonts,
+            contents,
+        );
+    }
+}
+
+// A laid out line starting at `start` on the baseline, in points
+fn generate_text_line(
+    line: &TextLine,
+    start: Point,
+    font_size: f32,
+    fonts: &DocumentFonts,
+    contents: &mut Vec<Op>,
+) {
+    let mut left = start.x.0;
+
+    for run in &line.runs {
+        let font = &fonts.caption[run.font];
+
+        contents.extend([
+            Op::StartTextSection,
+            Op::SetTextCursor {
+                pos: Point {
+                    x: Pt(left),
+                    y: start.y,
+                },
+            },
+            Op::SetFontSize {
+                size: Pt(font_size),
+                font: font.id.clone(),
+            },
+            Op::WriteText {
+                items: vec![TextItem::Text(run.text.clone())],
+                font: font.id.clone(),
+            },
+            Op::EndTextSection,
+        ]);
+        left += run.width;
+    }
+}
+
+// Flows text blocks down the page in the fonts of the caption chain,
+// starting a new page when one is full
+struct TextFlow<'a> {
+    page: Page,
+    fonts: &'a DocumentFonts,
+    faces: &'a [Face<'a>],
+    pages: Vec<PdfPage>,
+    contents: Vec<Op>,
+    cursor: f32,
+}
+
+impl<'a> TextFlow<'a> {
+    fn new(page: &Page, fonts: &'a DocumentFonts, faces: &'a [Face<'a>]) -> Self {
+        Self {
+            page: *page,
+            fonts,
+            faces,
+            pages: Vec::new(),
+            contents: Vec::new(),
+            cursor: page.top(),
+        }
+    }
+
+    fn write(&mut self, text: &str, font_size: f32) {
+        let max_width = Mm(self.page.text_width()).into_pt().0;
+        let line_height: Mm = Pt(font_size * TEXT_LINE_HEIGHT).into();
+
+        for line in layout_text(self.faces, text, font_size, max_width) {
+            if self.cursor - line_height.0 < self.page.margin && !self.contents.is_empty() {
+                self.break_page();
+            }
+
+            let baseline = Mm(self.cursor).into_pt().0 - font_size;
 
-    generate_lines(&mut contents, &sizes);
-    generate_digits(
-        calculation,
-        &roboto_font,
-        &roboto_font_id,
-        &mut contents,
-        &sizes,
+            generate_text_line(
+                &line,
+                Point {
+                    x: Mm(self.page.margin).into_pt(),
+                    y: Pt(baseline),
+                },
+                font_size,
+                self.fonts,
+                &mut self.contents,
+            );
+            self.cursor -= line_height.0;
+        }
+
+        self.cursor -= font_size * 0.2;
+    }
+
+    fn break_page(&mut self) {
+        let contents = std::mem::take(&mut self.contents);
+
+        self.pages.push(self.page.new_page(contents));
+        self.cursor = self.page.top();
+    }
+
+    fn finish(mut self) -> Vec<PdfPage> {
+        if !self.contents.is_empty() {
+            self.break_page();
+        }
+
+        self.pages
+    }
+}
+
+fn format_digits(digits: &[u16]) -> String {
+    digits
+        .iter()
+        .map(u16::to_string)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn generate_trace_pages(
+    trace: &Trace,
+    calculation: &MandalaGrid,
+    mut flow: TextFlow,
+) -> Vec<PdfPage> {
+    flow.write("Как получена мандала", 18.0);
+
+    if let Some(normalized) = &trace.normalized {
+        flow.write("Текст после нормализации", 14.0);
+        flow.write(normalized, 11.0);
+    }
+
+    if !trace.substitutions.is_empty() {
+        flow.write("Замены при нормализации", 14.0);
+        flow.write(
+            &trace
+                .substitutions
+                .iter()
+                .map(|substitution| format!("{} — {}", substitution.from, substitution.to))
+                .collect::<Vec<String>>()
+                .join(", "),
+            11.0,
+        );
+    }
+
+    flow.write("Значения букв", 14.0);
+    flow.write(
+        &trace
+            .letters
+            .iter()
+            .map(|(letter, value)| format!("{} = {}", letter, value))
+            .collect::<Vec<String>>()
+            .join(", "),
+        11.0,
     );
-    generate_text(&input, &roboto_font, &roboto_font_id, &mut contents);
 
-    let page = PdfPage::new(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), contents);
-    let pdf_bytes: Vec<u8> = document
-        .with_pages(vec![page])
-        .save(&PdfSaveOptions::default(), &mut Vec::new());
+    if !trace.padding.is_empty() {
+        flow.write("Дополнение короткого текста", 14.0);
+
+        for step in &trace.padding {
+            flow.write(
+                &format!("{} + {} = {}", step.left, step.right, step.sum),
+                11.0,
+            );
+        }
+    }
+
+    if !trace.reductions.is_empty() {
+        flow.write("Свёртка длинного текста", 14.0);
+
+        for row in &trace.reductions {
+            flow.write(&format_digits(row), 11.0);
+        }
+    }
+
+    flow.write("Исходная строка", 14.0);
+    flow.write(&format_digits(calculation.seed()), 11.0);
+
+    flow.finish()
+}
+
+// How often every digit occurs, and what the digits mean
+fn generate_statistics_pages(grid: &MandalaGrid, mut flow: TextFlow) -> Vec<PdfPage> {
+    let counts = grid.counts();
+    let total = counts.iter().sum::<usize>();
+    let most = counts.iter().copied().max().unwrap_or_default();
+    let digits_where = |matches: &dyn Fn(usize) -> bool| {
+        (1..)
+            .zip(&counts)
+            .filter(|&(_, &count)| matches(count))
+            .map(|(digit, _)| digit)
+            .collect::<Vec<u16>>()
+    };
+    let with_meaning = |digit: u16| match meaning(digit) {
+        Some(meaning) => format!("{} — {}", digit, meaning.keywords),
+        None => digit.to_string(),
+    };
+    flow.write("Цифры мандалы", 18.0);
+
+    flow.write("Вершина", 14.0);
+    flow.write(&with_meaning(grid.apex()), 11.0);
+
+    flow.write("Сколько раз встречается каждая цифра", 14.0);
+    for (digit, &count) in (1..).zip(&counts) {
+        flow.write(
+            &format!(
+                "{}: {} из {} ({:.0}%)",
+                digit,
+                count,
+                total,
+                count as f32 * 100.0 / total as f32
+            ),
+            11.0,
+        );
+    }
+
+    flow.write("Преобладают", 14.0);
+    flow.write(
+        &digits_where(&|count| count == most)
+            .into_iter()
+            .map(with_meaning)
+            .collect::<Vec<String>>()
+            .join("; "),
+        11.0,
+    );
+
+    let missing = digits_where(&|count| count == 0);
+
+    if !missing.is_empty() {
+        flow.write("Не встречаются", 14.0);
+        flow.write(
+            &missing
+                .into_iter()
+                .map(with_meaning)
+                .collect::<Vec<String>>()
+                .join("; "),
+            11.0,
+        );
+    }
+
+    flow.write("Толкование цифр", 14.0);
+    for digit in 1..=grid.modulus() {
+        match meaning(digit) {
+            Some(meaning) => {
+                flow.write(&format!("{} — {}", digit, meaning.keywords), 12.0);
+                flow.write(meaning.description, 11.0);
+            }
+            None => {
+                flow.write(&format!("{} — толкования нет", digit), 12.0);
+            }
+        }
+    }
+
+    flow.finish()
+}
+
+fn generate_rejected_pages(rejected: &[RejectedRow], mut flow: TextFlow) -> Vec<PdfPage> {
+    flow.write("Не удалось построить", 18.0);
 
-    let file_name = input.chars().take(100).collect::<String>();
-    let export_path = dirs::download_dir().unwrap().join(&format!("Мандала {}.pdf", file_name));
+    for row in rejected {
+        flow.write(&format!("Строка {}: «{}»", row.line, row.text), 14.0);
+        flow.write(&row.reason, 11.0);
+    }
 
-    std::fs::write(&export_path, pdf_bytes).map_err(|_| "Failed to write PDF file")?;
+    flow.finish()
+}
+
+// The triangle as calculated under a heading, the apex on top and the line
+// at the bottom
+fn generate_triangle_page(
+    grid: &MandalaGrid,
+    options: &PdfOptions,
+    page: &Page,
+    fonts: &DocumentFonts,
+) -> PdfPage {
+    let heading = fonts.text.shape_text(
+        "Треугольник мандалы",
+        &TextShapingOptions {
+            font_size: Pt(18.0),
+            max_width: Some(Mm(page.text_width()).into_pt()),
+            align: TextAlign::Left,
+            ..TextShapingOptions::default()
+        },
+    );
+    let heading_height: Mm = Pt(heading.height).into();
+    let top = page.top() - heading_height.0 - TRIANGLE_GAP;
+    let rows = grid.size() as f32;
+    let side = (page.text_width() / rows).min((top - page.margin) / rows);
+    let text_options = TextShapingOptions {
+        font_size: Pt(Mm(side).into_pt().0 * TRIANGLE_DIGIT_RATIO),
+        ..TextShapingOptions::default()
+    };
+    // A colouring book leaves the cells blank here as well
+    let coloring = match options.coloring_book {
+        Some(_) => CellColoring::Plain,
+        None => options.coloring,
+    };
+    let palette = Palette::for_modulus(grid.modulus());
+
+    let mut contents = heading
+        .get_ops(Point::new(Mm(page.margin), Mm(page.top())))
+        .to_vec();
 
-    Ok(export_path)
+    contents.extend(line_style(LINE_WIDTH));
+
+    for cell in grid.cells() {
+        let left = (page.width - (cell.row + 1) as f32 * side) / 2.0 + cell.col as f32 * side;
+        let bottom = top - (cell.row + 1) as f32 * side;
+        let corners = [
+            Vec2::new(left, bottom),
+            Vec2::new(left + side, bottom),
+            Vec2::new(left + side, bottom + side),
+            Vec2::new(left, bottom + side),
+        ];
+
+        match cell_color(&palette, cell.value, coloring) {
+            Some(col) => {
+                contents.push(Op::SetFillColor { col });
+                contents.push(fill_polygon(corners, PaintMode::FillStroke));
+                contents.push(fill_gray(0.0));
+            }
+            None => contents.push(fill_polygon(corners, PaintMode::Stroke)),
+        }
+
+        let text = fonts
+            .digits
+            .shape_text(&cell.value.to_string(), &text_options);
+        let width: Mm = Pt(text.width).into();
+        let height: Mm = Pt(text.height).into();
+        let position = Vec2::new(
+            left + (side - width.0) / 2.0,
+            bottom + (side + height.0) / 2.0,
+        );
+
+        contents.extend(text.get_ops(Point::new(Mm(position.x), Mm(position.y))));
+    }
+
+    page.new_page(contents)
 }
 
-fn generate_text(text: &str, font: &ParsedFont, font_id: &FontId, contents: &mut Vec<Op>) {
+fn generate_footer(
+    system: NumerologySystem,
+    page: &Page,
+    font: &EmbeddedFont,
+    contents: &mut Vec<Op>,
+) {
     let text_options = TextShapingOptions {
-        max_width: Some(Mm(190.0).into_pt()),
+        font_size: Pt(10.0),
+        max_width: Some(Mm(page.text_width()).into_pt()),
         align: TextAlign::Center,
         ..TextShapingOptions::default()
     };
-    let text = font.shape_text(text, &text_options, font_id);
+    let text = font.shape_text(&format!("Система нумерологии: {}", system), &text_options);
+
+    let top = page.margin + FOOTER_HEIGHT / 2.0;
 
-    contents.extend(text.get_ops(Point::new(Mm(10.0), Mm(50.0))));
+    contents.extend(text.get_ops(Point::new(Mm(page.margin), Mm(top))));
 }
 
 fn generate_digits(
-    digits: Vec<Vec<u16>>,
-    font: &ParsedFont,
-    font_id: &FontId,
+    digits: &MandalaGrid,
+    font_size: f32,
+    font: &EmbeddedFont,
     contents: &mut Vec<Op>,
     sizes: &Sizes,
 ) {
     let text_options = TextShapingOptions {
+        font_size: Pt(font_size),
         ..TextShapingOptions::default()
     };
 
     (0..6).for_each(|segment| {
-        (0..16).for_each(|row| {
-            (0..row + 1).for_each(|col| {
-                let digit = digits[15 - row as usize][col as usize];
-                let text = font.shape_text(DIGITS[digit as usize - 1], &text_options, font_id);
-
-                let x = (col as f32) * sizes.line_offset + sizes.half_line_offset;
-                let y = (row as f32) * sizes.segment_size + sizes.half_segment_size
-                    - (col as f32) * sizes.half_segment_size
-                    + sizes.quarter_segment_size;
-
-                let width: Mm = Pt(text.width).into();
-                let height: Mm = Pt(text.height).into();
-
-                let bounds_vector = Vec2::new(-width.0 / 2.0, height.0 / 2.0);
-                let initial_position_vector = Vec2::new(x, y);
-                let rotation_matrix = Mat2::from_angle((segment as f32) * 60.0_f32.to_radians());
-                let position = rotation_matrix * initial_position_vector
-                    + sizes.translation_vector
-                    + bounds_vector;
-
-                contents.extend(text.get_ops(Point::new(Mm(position.x), Mm(position.y))));
-            });
+        digits.cells().for_each(|cell| {
+            let text = font.shape_text(&cell.value.to_string(), &text_options);
+
+            let width: Mm = Pt(text.width).into();
+            let height: Mm = Pt(text.height).into();
+
+            let bounds_vector = Vec2::new(-width.0 / 2.0, height.0 / 2.0);
+            let position = sizes.cell_center(cell, segment) + bounds_vector;
+
+            contents.extend(text.get_ops(Point::new(Mm(position.x), Mm(position.y))));
         });
     });
 }
 
-fn generate_lines(contents: &mut Vec<Op>, sizes: &Sizes) {
-    (0..6).for_each(|sketch_segment| {
-        (0..17).for_each(|line_index| {
-            let x = sizes.line_offset * line_index as f32;
-            let y1 = sizes.half_segment_size * (line_index as f32);
-            let idx = if line_index == 0 { 0 } else { line_index - 1 };
-            let count = 16 - idx;
-            let y2 = y1 + (count as f32) * sizes.half_segment_size * 2.0;
-
-            let rotation = Mat2::from_angle(sketch_segment as f32 * 60.0_f32.to_radians());
-
-            let line_a_start = rotation * Vec2::new(x, y1) + sizes.translation_vector;
-            let line_a_end = rotation * Vec2::new(x, y2) + sizes.translation_vector;
-            let line_b_start = rotation * Vec2::new(-x, y1) + sizes.translation_vector;
-            let line_b_end = rotation * Vec2::new(-x, y2) + sizes.translation_vector;
-
-            contents.push(Op::DrawLine {
-                line: Line {
-                    points: vec![
-                        LinePoint {
-                            p: Point::new(Mm(line_a_start.x), Mm(line_a_start.y)),
-                            bezier: false,
-                        },
-                        LinePoint {
-                            p: Point::new(Mm(line_a_end.x), Mm(line_a_end.y)),
-                            bezier: false,
-                        },
-                    ],
-                    is_closed: true,
-                },
-            });
+fn generate_dots(grid: &MandalaGrid, contents: &mut Vec<Op>, sizes: &Sizes) {
+    let radius = sizes.segment_size * DOT_RATIO;
 
-            contents.push(Op::DrawLine {
-                line: Line {
-                    points: vec![
-                        LinePoint {
-                            p: Point::new(Mm(line_b_start.x), Mm(line_b_start.y)),
-                            bezier: false,
-                        },
-                        LinePoint {
-                            p: Point::new(Mm(line_b_end.x), Mm(line_b_end.y)),
-                            bezier: false,
-                        },
-                    ],
-                    is_closed: true,
-                },
+    for segment in 0..6 {
+        for cell in grid.cells() {
+            let center = sizes.cell_center(cell, segment);
+            let points = (0..16).map(|step| {
+                center + Vec2::from_angle(step as f32 * 22.5_f32.to_radians()) * radius
             });
+
+            contents.push(fill_polygon(points, PaintMode::Fill));
+        }
+    }
+}
+
+// A swatch of the palette colour next to every digit, in one row above the
+// footer
+fn generate_legend(grid: &MandalaGrid, page: &Page, font: &EmbeddedFont, contents: &mut Vec<Op>) {
+    let palette = Palette::for_modulus(grid.modulus());
+    let digits = palette.colors().len();
+    let step = page.text_width() / digits as f32;
+    let top = page.margin + FOOTER_HEIGHT + (LEGEND_HEIGHT + SWATCH_SIZE) / 2.0;
+    let text_options = TextShapingOptions {
+        font_size: Pt(11.0),
+        ..TextShapingOptions::default()
+    };
+
+    for (index, color) in palette.colors().iter().enumerate() {
+        let left = page.margin + step * index as f32 + (step - SWATCH_SIZE * 2.0) / 2.0;
+        let corners = [
+            Vec2::new(left, top - SWATCH_SIZE),
+            Vec2::new(left + SWATCH_SIZE, top - SWATCH_SIZE),
+            Vec2::new(left + SWATCH_SIZE, top),
+            Vec2::new(left, top),
+        ];
+
+        contents.push(Op::SetFillColor {
+            col: Color::Rgb(Rgb::new(
+                color.r as f32 / 255.0,
+                color.g as f32 / 255.0,
+                color.b as f32 / 255.0,
+                None,
+            )),
         });
-    });
+        contents.push(fill_polygon(corners, PaintMode::FillStroke));
+
+        let text = font.shape_text(&(index + 1).to_string(), &text_options);
+        let height: Mm = Pt(text.height).into();
+        let position = Vec2::new(
+            left + SWATCH_SIZE * 1.3,
+            top - (SWATCH_SIZE - height.0) / 2.0,
+        );
+
+        contents.push(fill_gray(0.0));
+        contents.extend(text.get_ops(Point::new(Mm(position.x), Mm(position.y))));
+    }
+}
+
+fn generate_cells(
+    grid: &MandalaGrid,
+    coloring: CellColoring,
+    contents: &mut Vec<Op>,
+    sizes: &Sizes,
+) {
+    let palette = Palette::for_modulus(grid.modulus());
+
+    for segment in 0..6 {
+        for cell in grid.cells() {
+            if let Some(col) = cell_color(&palette, cell.value, coloring) {
+                contents.push(Op::SetFillColor { col });
+                contents.push(fill_polygon(
+                    sizes.cell_corners(cell, segment),
+                    PaintMode::Fill,
+                ));
+            }
+        }
+    }
+}
+
+// Fill of a cell holding `value`, none when the cells stay white
+fn cell_color(palette: &Palette, value: u16, coloring: CellColoring) -> Option<Color> {
+    let share = match coloring {
+        CellColoring::Full => 1.0,
+        CellColoring::Tinted => TINT,
+        CellColoring::Plain => return None,
+    };
+    let channel = |value: u8| 1.0 - (1.0 - value as f32 / 255.0) * share;
+    let color = palette.color(value);
+
+    Some(Color::Rgb(Rgb::new(
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+        None,
+    )))
+}
+
+fn generate_lines(contents: &mut Vec<Op>, sizes: &Sizes) {
+    contents.extend(sizes.lines().map(|[start, end]| Op::DrawLine {
+        line: Line {
+            points: vec![
+                LinePoint {
+                    p: Point::new(Mm(start.x), Mm(start.y)),
+                    bezier: false,
+                },
+                LinePoint {
+                    p: Point::new(Mm(end.x), Mm(end.y)),
+                    bezier: false,
+                },
+            ],
+            is_closed: true,
+        },
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unusable_line_widths() {
+        assert!(ColoringBook::default().check().is_ok());
+
+        for line_width in [0.0, -3.0, f32::NAN, f32::INFINITY] {
+            let options = PdfOptions {
+                coloring_book: Some(ColoringBook {
+                    line_width,
+                    ..ColoringBook::default()
+                }),
+                ..PdfOptions::default()
+            };
+
+            assert!(
+                matches!(
+                    render_mandalas_pdf(&[], &[], &options),
+                    Err(Error::InvalidLineWidth { .. })
+                ),
+                "{line_width}"
+            );
+        }
+    }
+
+    #[test]
+    fn writes_properties_as_utf16_hex_strings() {
+        assert_eq!(text_string("Ян"), "FEFF042F043D");
+
+        let mut bytes = format!(
+            "<</Title({})/Author(FEFF)/Subject(Plain)>>",
+            text_string("Ян")
+        )
+        .into_bytes();
+        let length = bytes.len();
+
+        mark_hex_strings(&mut bytes);
+
+        assert_eq!(bytes.len(), length);
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "<</Title<FEFF042F043D>/Author<FEFF>/Subject(Plain)>>"
+        );
+    }
+
+    #[test]
+    fn replaces_document_ids_with_a_hash() {
+        let document = |id: &str| {
+            format!(
+                "%PDF-1.3 body\ntrailer\n<</ID[({0})({0})]>>\nstartxref\n9\n%%EOF",
+                id
+            )
+            .into_bytes()
+        };
+        let mut first = document(&"A".repeat(32));
+        let mut second = document(&"B".repeat(32));
+        let length = first.len();
+
+        replace_document_ids(&mut first);
+        replace_document_ids(&mut second);
+
+        assert_eq!(first, second);
+        assert_eq!(first.len(), length);
+        assert_ne!(first, document(&"A".repeat(32)));
+    }
+
+    #[test]
+    fn leaves_documents_without_ids_alone() {
+        let mut bytes = b"%PDF-1.3 trailer <</ID[(short)(short)]>>".to_vec();
+        let original = bytes.clone();
+
+        replace_document_ids(&mut bytes);
+
+        assert_eq!(bytes, original);
+    }
 }
@@ -0,0 +1,272 @@
+//! Raster rendering of a mandala into PNG, JPEG or WebP.
+//!
+//! The SVG drawing is rasterised on the CPU, so no graphics card or system
+//! fonts are needed.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use image::ExtendedColorType;
+use image::ImageEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use resvg::tiny_skia::{Color, Pixmap, Transform};
+use resvg::usvg::{Options, Tree};
+
+use crate::caption::CaptionOptions;
+use crate::error::{Error, Result};
+use crate::fonts::Fonts;
+use crate::grid::MandalaGrid;
+use crate::palette::Rgb;
+use crate::svg::render_mandala_svg;
+
+/// Longest side of a rendered image, in pixels.
+pub const MAX_IMAGE_SIZE: u32 = 16384;
+
+const MM_PER_INCH: f32 = 25.4;
+const JPEG_QUALITY: u8 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum RasterFormat {
+    /// Lossless, keeps transparency and the input text in its metadata.
+    #[default]
+    Png,
+    /// Lossy and always opaque.
+    Jpeg,
+    /// Lossless, keeps transparency.
+    Webp,
+}
+
+impl RasterFormat {
+    pub const ALL: [RasterFormat; 3] = [RasterFormat::Png, RasterFormat::Jpeg, RasterFormat::Webp];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RasterFormat::Png => "png",
+            RasterFormat::Jpeg => "jpg",
+            RasterFormat::Webp => "webp",
+        }
+    }
+
+    /// The format of a file with this extension, in any case.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(RasterFormat::Png),
+            "jpg" | "jpeg" => Some(RasterFormat::Jpeg),
+            "webp" => Some(RasterFormat::Webp),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RasterFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RasterFormat::Png => "PNG",
+            RasterFormat::Jpeg => "JPEG",
+            RasterFormat::Webp => "WebP",
+        })
+    }
+}
+
+/// How large the image is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    /// Width in pixels, the height follows the drawing.
+    Width(u32),
+    /// Dots per inch of the drawing printed at its natural size, as wide as
+    /// an A4 page.
+    Dpi(f32),
+}
+
+impl Resolution {
+    /// Sizes offered by the application: square posts, full HD screens and
+    /// print.
+    pub const PRESETS: [Resolution; 6] = [
+        Resolution::Width(1080),
+        Resolution::Width(2048),
+        Resolution::Width(4096),
+        Resolution::Dpi(150.0),
+        Resolution::Dpi(300.0),
+        Resolution::Dpi(600.0),
+    ];
+}
+
+impl Default for Resolution {
+    fn default() -> Self {
+        Resolution::Width(2048)
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolution::Width(width) => write!(f, "{} px", width),
+            Resolution::Dpi(dpi) => write!(f, "{} dpi", dpi),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RasterOptions {
+    pub format: RasterFormat,
+    pub resolution: Resolution,
+    /// `None` leaves the background transparent. JPEG has no transparency
+    /// and falls back to white.
+    pub background: Option<Rgb>,
+    pub fonts: Fonts,
+    pub caption: CaptionOptions,
+}
+
+/// Saves the mandala into `path`, returning it back once written.
+pub async fn save_mandala_raster(
+    path: PathBuf,
+    calculation: MandalaGrid,
+    caption: String,
+    options: RasterOptions,
+) -> Result<PathBuf> {
+    write_mandala_raster(&path, &calculation, &caption, &options)?;
+
+    Ok(path)
+}
+
+pub fn write_mandala_raster(
+    path: &Path,
+    calculation: &MandalaGrid,
+    caption: &str,
+    options: &RasterOptions,
+) -> Result<()> {
+    let bytes = render_mandala_raster(calculation, caption, options)?;
+
+    std::fs::write(path, bytes).map_err(|source| Error::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// The same drawing as [`render_mandala_svg`], encoded in `options.format`.
+/// A PNG additionally carries `caption` as its title.
+pub fn render_mandala_raster(
+    calculation: &MandalaGrid,
+    caption: &str,
+    options: &RasterOptions,
+) -> Result<Vec<u8>> {
+    let svg = render_mandala_svg(calculation, caption, &options.fonts, &options.caption)?;
+    let svg_options = Options::default();
+    let tree = Tree::from_str(&svg, &svg_options).map_err(|_| Error::Rasterize)?;
+
+    // usvg measures the drawing in CSS pixels, `svg_options.dpi` per inch
+    let size = tree.size();
+    let width_inches = size.width() / svg_options.dpi;
+    let scale = match options.resolution {
+        Resolution::Width(width) => width as f32 / size.width(),
+        Resolution::Dpi(dpi) => dpi * width_inches / size.width(),
+    };
+    let width = (size.width() * scale).round();
+    let height = (size.height() * scale).round();
+
+    if !(1.0..=MAX_IMAGE_SIZE as f32).contains(&width)
+        || !(1.0..=MAX_IMAGE_SIZE as f32).contains(&height)
+    {
+        return Err(Error::ImageSize {
+            width: width.max(0.0) as u32,
+            height: height.max(0.0) as u32,
+        });
+    }
+
+    let mut pixmap = Pixmap::new(width as u32, height as u32).ok_or(Error::Rasterize)?;
+    let background = match options.format {
+        RasterFormat::Jpeg => Some(options.background.unwrap_or(Rgb::from_hex(0xFFFFFF))),
+        _ => options.background,
+    };
+
+    if let Some(color) = background {
+        pixmap.fill(Color::from_rgba8(color.r, color.g, color.b, 255));
+    }
+
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let pixel = pixel.demultiply();
+
+            [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+        })
+        .collect::<Vec<u8>>();
+    let dpi = width / width_inches;
+
+    match options.format {
+        RasterFormat::Png => encode_png(&rgba, pixmap.width(), pixmap.height(), dpi, caption),
+        RasterFormat::Jpeg => {
+            let rgb = rgba
+                .chunks_exact(4)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                .collect::<Vec<u8>>();
+            let mut bytes = Vec::new();
+
+            JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY)
+                .write_image(
+                    &rgb,
+                    pixmap.width(),
+                    pixmap.height(),
+                    ExtendedColorType::Rgb8,
+                )
+                .map_err(|_| Error::Rasterize)?;
+
+            Ok(bytes)
+        }
+        RasterFormat::Webp => {
+            let mut bytes = Vec::new();
+
+            WebPEncoder::new_lossless(&mut bytes)
+                .write_image(
+                    &rgba,
+                    pixmap.width(),
+                    pixmap.height(),
+                    ExtendedColorType::Rgba8,
+                )
+                .map_err(|_| Error::Rasterize)?;
+
+            Ok(bytes)
+        }
+    }
+}
+
+// PNG with the physical size and the caption kept in UTF-8 text chunks
+fn encode_png(rgba: &[u8], width: u32, height: u32, dpi: f32, caption: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    let pixels_per_meter = (dpi / MM_PER_INCH * 1000.0).round() as u32;
+
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_pixel_dims(Some(png::PixelDimensions {
+        xppu: pixels_per_meter,
+        yppu: pixels_per_meter,
+        unit: png::Unit::Meter,
+    }));
+
+    if !caption.is_empty() {
+        encoder
+            .add_itxt_chunk("Title".to_string(), caption.to_string())
+            .map_err(|_| Error::Rasterize)?;
+    }
+    encoder
+        .add_itxt_chunk("Software".to_string(), "Mandala".to_string())
+        .map_err(|_| Error::Rasterize)?;
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rgba))
+        .map_err(|_| Error::Rasterize)?;
+
+    Ok(bytes)
+}
@@ -0,0 +1,398 @@
+//! SVG rendering of a mandala for further work in vector editors.
+//!
+//! The drawing matches the PDF page: the same hexagonal layout, with every
+//! cell filled with the colour of its digit. Text is converted to outlines
+//! so the file looks the same without the fonts installed. Each copy of the
+//! triangle is a separate group, `segment-1` to `segment-6`, holding its
+//! `cells` and `digits`; the grid lines and the caption have groups of their
+//! own.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use ::svg::Document;
+use ::svg::node::Text;
+use ::svg::node::element::{Definitions, Group, Line, Path as SvgPath, Polygon, Title, Use};
+use glam::Vec2;
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+use crate::caption::{CaptionBlock, CaptionOptions, CaptionPosition};
+use crate::error::{Error, Result};
+use crate::fonts::{Fonts, LoadedFont};
+use crate::grid::MandalaGrid;
+use crate::layout::Sizes;
+use crate::palette::Palette;
+
+const MM_PER_PT: f32 = 25.4 / 72.0;
+
+// The drawing is as wide as the mandala on the A4 page
+const SKETCH_WIDTH: f32 = 210.0;
+const TEXT_MARGIN: f32 = 10.0;
+const LINE_WIDTH: f32 = 0.5 * MM_PER_PT;
+
+// Same digit size as in the PDF, in points per millimetre of cell
+const DIGIT_FONT_RATIO: f32 = 12.0 * 33.0 / SKETCH_WIDTH;
+
+/// Saves the mandala into `path`, returning it back once written.
+pub async fn save_mandala_svg(
+    path: PathBuf,
+    calculation: MandalaGrid,
+    caption: String,
+    fonts: Fonts,
+    caption_options: CaptionOptions,
+) -> Result<PathBuf> {
+    write_mandala_svg(&path, &calculation, &caption, &fonts, &caption_options)?;
+
+    Ok(path)
+}
+
+pub fn write_mandala_svg(
+    path: &Path,
+    calculation: &MandalaGrid,
+    caption: &str,
+    fonts: &Fonts,
+    caption_options: &CaptionOptions,
+) -> Result<()> {
+    let svg = render_mandala_svg(calculation, caption, fonts, caption_options)?;
+
+    std::fs::write(path, svg).map_err(|source| Error::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// The mandala with `caption` set as `caption_options` say, as an SVG
+/// document sized in millimetres. Without a caption only the drawing is left.
+pub fn render_mandala_svg(
+    calculation: &MandalaGrid,
+    caption: &str,
+    fonts: &Fonts,
+    caption_options: &CaptionOptions,
+) -> Result<String> {
+    caption_options.check()?;
+
+    let digits_font = fonts.digits.load()?;
+    let caption_chain = fonts.caption_chain(caption_options.texts(caption), [])?;
+
+    digits_font.check(
+        &calculation
+            .cells()
+            .map(|cell| cell.value.to_string())
+            .collect::<String>(),
+    )?;
+
+    let caption_faces = caption_chain
+        .iter()
+        .map(LoadedFont::face)
+        .collect::<Result<Vec<Face>>>()?;
+    let mut digit_glyphs = Glyphs::new(digits_font.face()?, "digit".to_string());
+    let mut caption_glyphs = caption_faces
+        .iter()
+        .enumerate()
+        .map(|(index, face)| Glyphs::new(face.clone(), format!("caption-{}", index)))
+        .collect::<Vec<Glyphs>>();
+
+    // Laid out in points like on the PDF page, drawn in millimetres
+    let caption_block = CaptionBlock::new(
+        &caption_faces,
+        caption,
+        caption_options,
+        (SKETCH_WIDTH - TEXT_MARGIN * 2.0) / MM_PER_PT,
+    );
+    let caption_room = match caption_block.lines.len() {
+        0 => 0.0,
+        _ => TEXT_MARGIN * 2.0 + caption_block.height * MM_PER_PT,
+    };
+    let height = SKETCH_WIDTH + caption_room;
+    let (sketch_top, caption_top) = match caption_options.position {
+        CaptionPosition::Above => (caption_room, TEXT_MARGIN),
+        _ => (0.0, SKETCH_WIDTH + TEXT_MARGIN),
+    };
+
+    // Layout coordinates point up, SVG ones down
+    let sizes = Sizes::new(
+        calculation.size(),
+        SKETCH_WIDTH,
+        Vec2::new(SKETCH_WIDTH / 2.0, height - sketch_top - SKETCH_WIDTH / 2.0),
+    );
+    let to_svg = |point: Vec2| Vec2::new(point.x, height - point.y);
+
+    let palette = Palette::for_modulus(calculation.modulus());
+    let digit_font_size = sizes.segment_size * DIGIT_FONT_RATIO * MM_PER_PT;
+
+    let mut document = Document::new()
+        .set("xmlns:xlink", "http://www.w3.org/1999/xlink")
+        .set("width", format!("{}mm", number(SKETCH_WIDTH)))
+        .set("height", format!("{}mm", number(height)))
+        .set(
+            "viewBox",
+            format!("0 0 {} {}", number(SKETCH_WIDTH), number(height)),
+        );
+
+    if !caption.is_empty() {
+        document = document.add(Title::new().add(Text::new(escape(caption))));
+    }
+
+    let mut segments = Vec::new();
+
+    for segment in 0..6 {
+        let mut cells = Group::new().set("class", "cells").set("stroke", "none");
+        let mut digits = Group::new().set("class", "digits").set("fill", "#000");
+
+        for cell in calculation.cells() {
+            let corners = sizes
+                .cell_corners(cell, segment)
+                .map(&to_svg)
+                .iter()
+                .map(|corner| format!("{},{}", number(corner.x), number(corner.y)))
+                .collect::<Vec<String>>()
+                .join(" ");
+            let color = palette.color(cell.value);
+
+            cells = cells.add(Polygon::new().set("points", corners).set(
+                "fill",
+                format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b),
+            ));
+
+            digits = digits.add(outline_text(
+                &cell.value.to_string(),
+                digit_font_size,
+                to_svg(sizes.cell_center(cell, segment)),
+                Anchor::Center,
+                &mut digit_glyphs,
+            ));
+        }
+
+        segments.push(
+            Group::new()
+                .set("id", format!("segment-{}", segment + 1))
+                .add(cells)
+                .add(digits),
+        );
+    }
+
+    let lines = sizes.lines().fold(
+        Group::new()
+            .set("id", "grid")
+            .set("stroke", "#000")
+            .set("stroke-width", number(LINE_WIDTH))
+            .set("stroke-linecap", "round"),
+        |group, [start, end]| {
+            let (start, end) = (to_svg(start), to_svg(end));
+
+            group.add(
+                Line::new()
+                    .set("x1", number(start.x))
+                    .set("y1", number(start.y))
+                    .set("x2", number(end.x))
+                    .set("y2", number(end.y)),
+            )
+        },
+    );
+
+    let mut caption_group = Group::new().set("id", "caption").set("fill", "#000");
+
+    for line in &caption_block.lines {
+        let baseline = caption_top + line.baseline * MM_PER_PT;
+        let font_size = line.font_size * MM_PER_PT;
+        let mut left = (SKETCH_WIDTH - line.text.width * MM_PER_PT) / 2.0;
+
+        for run in &line.text.runs {
+            let width = run.width * MM_PER_PT;
+
+            caption_group = caption_group.add(outline_text(
+                &run.text,
+                font_size,
+                Vec2::new(left + width / 2.0, baseline),
+                Anchor::Baseline,
+                &mut caption_glyphs[run.font],
+            ));
+            left += width;
+        }
+    }
+
+    let definitions = caption_glyphs.iter().fold(
+        digit_glyphs.define(Definitions::new()),
+        |definitions, glyphs| glyphs.define(definitions),
+    );
+
+    document = document.add(definitions);
+    document = segments
+        .into_iter()
+        .fold(document, |document, segment| document.add(segment));
+    document = document.add(lines);
+
+    if !caption_block.lines.is_empty() {
+        document = document.add(caption_group);
+    }
+
+    Ok(document.to_string())
+}
+
+// Keeps coordinates short, a thousandth of a millimetre is plenty
+fn number(value: f32) -> String {
+    let formatted = format!("{:.3}", value);
+
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+// Text nodes are written as is, so markup characters are escaped beforehand
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Collects a glyph outline as SVG path data in font units, y pointing up
+#[derive(Default)]
+struct PathData(String);
+
+impl OutlineBuilder for PathData {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let _ = write!(self.0, "M{} {}", x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let _ = write!(self.0, "L{} {}", x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let _ = write!(self.0, "Q{} {} {} {}", x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let _ = write!(self.0, "C{} {} {} {} {} {}", x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        self.0.push('Z');
+    }
+}
+
+// Glyphs of one font used in the drawing, defined once and referenced by
+// the text
+struct Glyphs<'a> {
+    face: Face<'a>,
+    prefix: String,
+    used: BTreeSet<u16>,
+}
+
+impl<'a> Glyphs<'a> {
+    fn new(face: Face<'a>, prefix: String) -> Self {
+        Self {
+            face,
+            prefix,
+            used: BTreeSet::new(),
+        }
+    }
+
+    fn id(&self, glyph: GlyphId) -> String {
+        format!("{}-glyph-{}", self.prefix, glyph.0)
+    }
+
+    // Adds the outlines of the used glyphs to `definitions`
+    fn define(&self, definitions: Definitions) -> Definitions {
+        self.used.iter().fold(definitions, |definitions, &id| {
+            let mut outline = PathData::default();
+
+            match self.face.outline_glyph(GlyphId(id), &mut outline) {
+                Some(_) => definitions.add(
+                    SvgPath::new()
+                        .set("id", self.id(GlyphId(id)))
+                        .set("d", outline.0),
+                ),
+                None => definitions,
+            }
+        })
+    }
+}
+
+// Where `position` lies relative to an outlined text
+enum Anchor {
+    /// The middle of the glyph boxes.
+    Center,
+    /// The middle of the baseline.
+    Baseline,
+}
+
+// Glyphs of `text` with their offsets in font units, and the total advance
+fn layout_glyphs(face: &Face, text: &str) -> (Vec<(GlyphId, f32)>, f32) {
+    let mut advance = 0.0;
+    let glyphs = text
+        .chars()
+        .map(|symbol| {
+            let id = face.glyph_index(symbol).unwrap_or(GlyphId(0));
+            let offset = advance;
+
+            advance += face.glyph_hor_advance(id).unwrap_or_default() as f32;
+            (id, offset)
+        })
+        .collect();
+
+    (glyphs, advance)
+}
+
+// Text as references to the glyph outlines, `glyphs` collects the ones used
+fn outline_text(
+    text: &str,
+    font_size: f32,
+    position: Vec2,
+    anchor: Anchor,
+    glyphs: &mut Glyphs,
+) -> Group {
+    let face = &glyphs.face;
+    let scale = font_size / face.units_per_em() as f32;
+    let (layout, advance) = layout_glyphs(face, text);
+
+    let middle = match anchor {
+        Anchor::Center => {
+            let (bottom, top) = layout
+                .iter()
+                .filter_map(|(id, _)| face.glyph_bounding_box(*id))
+                .fold((f32::MAX, f32::MIN), |(bottom, top), bounds| {
+                    (
+                        bottom.min(bounds.y_min as f32),
+                        top.max(bounds.y_max as f32),
+                    )
+                });
+
+            if bottom <= top {
+                (bottom + top) / 2.0
+            } else {
+                0.0
+            }
+        }
+        Anchor::Baseline => 0.0,
+    };
+
+    let origin = Vec2::new(
+        position.x - advance * scale / 2.0,
+        position.y + middle * scale,
+    );
+
+    layout.into_iter().fold(
+        Group::new().set(
+            "transform",
+            format!(
+                "translate({} {}) scale({} {})",
+                number(origin.x),
+                number(origin.y),
+                scale,
+                -scale
+            ),
+        ),
+        |group, (id, offset)| {
+            glyphs.used.insert(id.0);
+
+            group.add(
+                Use::new()
+                    .set("xlink:href", format!("#{}", glyphs.id(id)))
+                    .set("x", offset),
+            )
+        },
+    )
+}
@@ -0,0 +1,394 @@
+//! Caption layout shared by the renderers.
+//!
+//! Neither printpdf nor the SVG outlines shape text, they map every
+//! character to a glyph on their own. The caption is therefore prepared
+//! here: wrapped into lines, Arabic letters replaced with their joined
+//! presentation forms, every line put into visual order and split into runs
+//! of the first font of the chain able to draw them.
+
+use ttf_parser::Face;
+use unicode_bidi::ParagraphBidiInfo;
+use unicode_bidi_mirroring::get_mirrored;
+
+use crate::fonts::{has_outline, is_drawn, is_ignorable};
+
+/// Text drawn with one font of the chain.
+pub(crate) struct TextRun {
+    /// Index of the font in the chain.
+    pub font: usize,
+    pub text: String,
+    /// Advance of the run, in the units of the font size.
+    pub width: f32,
+}
+
+/// A line of the caption, runs left to right.
+pub(crate) struct TextLine {
+    pub runs: Vec<TextRun>,
+    pub width: f32,
+}
+
+/// Lays `text` out into lines at most `max_width` wide, breaking lines at
+/// spaces and keeping the line breaks of the text.
+pub(crate) fn layout_text(
+    faces: &[Face],
+    text: &str,
+    font_size: f32,
+    max_width: f32,
+) -> Vec<TextLine> {
+    let width = |line: &str| {
+        split_runs(faces, line, font_size)
+            .iter()
+            .map(|run| run.width)
+            .sum::<f32>()
+    };
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+
+            if width(&candidate) > max_width && !line.is_empty() {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+
+    lines
+        .iter()
+        .map(|line| {
+            let has_glyph = |symbol| faces.iter().any(|face| has_outline(face, symbol));
+            let runs = split_runs(
+                faces,
+                &visual_order(&join_arabic(line, has_glyph)),
+                font_size,
+            );
+
+            TextLine {
+                width: runs.iter().map(|run| run.width).sum(),
+                runs,
+            }
+        })
+        .collect()
+}
+
+// Runs of characters sharing a font. A character stays with the font of the
+// previous one while that font has it, so marks keep to their letters.
+fn split_runs(faces: &[Face], text: &str, font_size: f32) -> Vec<TextRun> {
+    let mut runs: Vec<TextRun> = Vec::new();
+
+    for symbol in text.chars().filter(|&symbol| !is_ignorable(symbol)) {
+        let current = runs.last().map(|run| run.font);
+        let font = match current {
+            Some(font) if !is_drawn(symbol) || has_outline(&faces[font], symbol) => font,
+            _ => (0..faces.len())
+                .find(|&font| has_outline(&faces[font], symbol))
+                .or(current)
+                .unwrap_or(0),
+        };
+
+        let face = &faces[font];
+        let advance = face
+            .glyph_index(symbol)
+            .and_then(|glyph| face.glyph_hor_advance(glyph))
+            .unwrap_or_default();
+        let width = advance as f32 * font_size / face.units_per_em() as f32;
+
+        match runs.last_mut() {
+            Some(run) if run.font == font => {
+                run.text.push(symbol);
+                run.width += width;
+            }
+            _ => runs.push(TextRun {
+                font,
+                text: symbol.to_string(),
+                width,
+            }),
+        }
+    }
+
+    runs
+}
+
+// Reorders a line for drawing left to right, reversing the right-to-left
+// parts and mirroring their brackets
+fn visual_order(line: &str) -> String {
+    let bidi = ParagraphBidiInfo::new(line, None);
+
+    if !bidi.has_rtl() {
+        return line.to_string();
+    }
+
+    let (levels, runs) = bidi.visual_runs(0..line.len());
+
+    runs.into_iter()
+        .map(|run| {
+            let text = &line[run.clone()];
+
+            if levels[run.start].is_rtl() {
+                text.chars()
+                    .rev()
+                    .map(|symbol| get_mirrored(symbol).unwrap_or(symbol))
+                    .collect()
+            } else {
+                text.to_string()
+            }
+        })
+        .collect()
+}
+
+const LAM: char = '\u{0644}';
+const TATWEEL: char = '\u{0640}';
+
+// Presentation forms of the Arabic letters: isolated, final, initial and
+// medial. Letters joining only the preceding one have no initial and medial
+// forms.
+const ARABIC_FORMS: [(char, [u32; 4]); 43] = [
+    ('\u{0621}', [0xFE80, 0, 0, 0]),
+    ('\u{0622}', [0xFE81, 0xFE82, 0, 0]),
+    ('\u{0623}', [0xFE83, 0xFE84, 0, 0]),
+    ('\u{0624}', [0xFE85, 0xFE86, 0, 0]),
+    ('\u{0625}', [0xFE87, 0xFE88, 0, 0]),
+    ('\u{0626}', [0xFE89, 0xFE8A, 0xFE8B, 0xFE8C]),
+    ('\u{0627}', [0xFE8D, 0xFE8E, 0, 0]),
+    ('\u{0628}', [0xFE8F, 0xFE90, 0xFE91, 0xFE92]),
+    ('\u{0629}', [0xFE93, 0xFE94, 0, 0]),
+    ('\u{062A}', [0xFE95, 0xFE96, 0xFE97, 0xFE98]),
+    ('\u{062B}', [0xFE99, 0xFE9A, 0xFE9B, 0xFE9C]),
+    ('\u{062C}', [0xFE9D, 0xFE9E, 0xFE9F, 0xFEA0]),
+    ('\u{062D}', [0xFEA1, 0xFEA2, 0xFEA3, 0xFEA4]),
+    ('\u{062E}', [0xFEA5, 0xFEA6, 0xFEA7, 0xFEA8]),
+    ('\u{062F}', [0xFEA9, 0xFEAA, 0, 0]),
+    ('\u{0630}', [0xFEAB, 0xFEAC, 0, 0]),
+    ('\u{0631}', [0xFEAD, 0xFEAE, 0, 0]),
+    ('\u{0632}', [0xFEAF, 0xFEB0, 0, 0]),
+    ('\u{0633}', [0xFEB1, 0xFEB2, 0xFEB3, 0xFEB4]),
+    ('\u{0634}', [0xFEB5, 0xFEB6, 0xFEB7, 0xFEB8]),
+    ('\u{0635}', [0xFEB9, 0xFEBA, 0xFEBB, 0xFEBC]),
+    ('\u{0636}', [0xFEBD, 0xFEBE, 0xFEBF, 0xFEC0]),
+    ('\u{0637}', [0xFEC1, 0xFEC2, 0xFEC3, 0xFEC4]),
+    ('\u{0638}', [0xFEC5, 0xFEC6, 0xFEC7, 0xFEC8]),
+    ('\u{0639}', [0xFEC9, 0xFECA, 0xFECB, 0xFECC]),
+    ('\u{063A}', [0xFECD, 0xFECE, 0xFECF, 0xFED0]),
+    ('\u{0641}', [0xFED1, 0xFED2, 0xFED3, 0xFED4]),
+    ('\u{0642}', [0xFED5, 0xFED6, 0xFED7, 0xFED8]),
+    ('\u{0643}', [0xFED9, 0xFEDA, 0xFEDB, 0xFEDC]),
+    ('\u{0644}', [0xFEDD, 0xFEDE, 0xFEDF, 0xFEE0]),
+    ('\u{0645}', [0xFEE1, 0xFEE2, 0xFEE3, 0xFEE4]),
+    ('\u{0646}', [0xFEE5, 0xFEE6, 0xFEE7, 0xFEE8]),
+    ('\u{0647}', [0xFEE9, 0xFEEA, 0xFEEB, 0xFEEC]),
+    ('\u{0648}', [0xFEED, 0xFEEE, 0, 0]),
+    ('\u{0649}', [0xFEEF, 0xFEF0, 0, 0]),
+    ('\u{064A}', [0xFEF1, 0xFEF2, 0xFEF3, 0xFEF4]),
+    // Persian letters
+    ('\u{067E}', [0xFB56, 0xFB57, 0xFB58, 0xFB59]),
+    ('\u{0686}', [0xFB7A, 0xFB7B, 0xFB7C, 0xFB7D]),
+    ('\u{0698}', [0xFB8A, 0xFB8B, 0, 0]),
+    ('\u{06A9}', [0xFB8E, 0xFB8F, 0xFB90, 0xFB91]),
+    ('\u{06AF}', [0xFB92, 0xFB93, 0xFB94, 0xFB95]),
+    ('\u{06CC}', [0xFBFC, 0xFBFD, 0xFBFE, 0xFBFF]),
+    ('\u{06C0}', [0xFBA4, 0xFBA5, 0, 0]),
+];
+
+// Ligatures of lam with the following alef: isolated and final
+const LAM_ALEF: [(char, [u32; 2]); 4] = [
+    ('\u{0622}', [0xFEF5, 0xFEF6]),
+    ('\u{0623}', [0xFEF7, 0xFEF8]),
+    ('\u{0625}', [0xFEF9, 0xFEFA]),
+    ('\u{0627}', [0xFEFB, 0xFEFC]),
+];
+
+fn arabic_forms(symbol: char) -> Option<[u32; 4]> {
+    ARABIC_FORMS
+        .iter()
+        .find(|(letter, _)| *letter == symbol)
+        .map(|(_, forms)| *forms)
+}
+
+// Vowel marks and other signs placed on a letter, skipped when deciding
+// how letters join
+fn is_transparent(symbol: char) -> bool {
+    matches!(symbol, '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{06D6}'..='\u{06ED}')
+}
+
+fn joins_previous(symbol: char) -> bool {
+    symbol == TATWEEL || arabic_forms(symbol).is_some_and(|forms| forms[1] != 0)
+}
+
+fn joins_next(symbol: char) -> bool {
+    symbol == TATWEEL || arabic_forms(symbol).is_some_and(|forms| forms[2] != 0)
+}
+
+// Replaces Arabic letters with the presentation forms matching their
+// neighbours. Forms `has_glyph` rejects are left as plain letters.
+fn join_arabic(text: &str, has_glyph: impl Fn(char) -> bool) -> String {
+    let symbols = text.chars().collect::<Vec<char>>();
+    let letter_before = |index: usize| {
+        symbols[..index]
+            .iter()
+            .rev()
+            .find(|&&symbol| !is_transparent(symbol))
+            .copied()
+    };
+    let letter_after = |index: usize| {
+        symbols[index + 1..]
+            .iter()
+            .find(|&&symbol| !is_transparent(symbol))
+            .copied()
+    };
+    let form = |code: u32, fallback: char| {
+        char::from_u32(code)
+            .filter(|&form| code != 0 && has_glyph(form))
+            .unwrap_or(fallback)
+    };
+
+    let mut joined = String::new();
+    let mut index = 0;
+
+    while index < symbols.len() {
+        let symbol = symbols[index];
+        let after_previous = letter_before(index).is_some_and(joins_next);
+
+        if symbol == LAM
+            && let Some(&next) = symbols.get(index + 1)
+            && let Some((_, ligature)) = LAM_ALEF.iter().find(|(alef, _)| *alef == next)
+            && let Some(form) = char::from_u32(ligature[usize::from(after_previous)])
+                .filter(|&form| has_glyph(form))
+        {
+            joined.push(form);
+            index += 2;
+            continue;
+        }
+
+        match arabic_forms(symbol) {
+            Some(forms) => {
+                let previous = after_previous && joins_previous(symbol);
+                let next = joins_next(symbol) && letter_after(index).is_some_and(joins_previous);
+                let code = match (previous, next) {
+                    (false, false) => forms[0],
+                    (true, false) => forms[1],
+                    (false, true) => forms[2],
+                    (true, true) => forms[3],
+                };
+
+                joined.push(form(code, symbol));
+            }
+            None => joined.push(symbol),
+        }
+
+        index += 1;
+    }
+
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fonts::{FontSource, Fonts, LoadedFont};
+
+    fn codes(text: &str) -> Vec<u32> {
+        text.chars().map(u32::from).collect()
+    }
+
+    #[test]
+    fn joins_arabic_letters() {
+        // Beh, yeh and teh: initial, medial and final
+        assert_eq!(
+            codes(&join_arabic("بيت", |_| true)),
+            [0xFE91, 0xFEF4, 0xFE96]
+        );
+        assert_eq!(codes(&join_arabic("ب", |_| true)), [0xFE8F]);
+        // Dal joins the preceding letter only, the beh after it stands alone
+        assert_eq!(
+            codes(&join_arabic("بدب", |_| true)),
+            [0xFE91, 0xFEAA, 0xFE8F]
+        );
+        // A vowel mark does not break the join
+        assert_eq!(
+            codes(&join_arabic("بَت", |_| true)),
+            [0xFE91, 0x064E, 0xFE96]
+        );
+    }
+
+    #[test]
+    fn joins_lam_and_alef() {
+        assert_eq!(codes(&join_arabic("لا", |_| true)), [0xFEFB]);
+        assert_eq!(codes(&join_arabic("سلا", |_| true)), [0xFEB3, 0xFEFC]);
+    }
+
+    #[test]
+    fn keeps_letters_without_presentation_glyphs() {
+        assert_eq!(join_arabic("سلا", |_| false), "سلا");
+    }
+
+    #[test]
+    fn reverses_right_to_left_runs() {
+        assert_eq!(visual_order("Анна"), "Анна");
+        assert_eq!(visual_order("abc שלום def"), "abc םולש def");
+        assert_eq!(visual_order("שלום 12"), "12 םולש");
+    }
+
+    #[test]
+    fn mirrors_brackets_in_right_to_left_runs() {
+        assert_eq!(visual_order("שלום (עולם)"), "(םלוע) םולש");
+        assert_eq!(visual_order("a (b) c"), "a (b) c");
+    }
+
+    // Roboto has no Hebrew, the chain takes it from an installed font
+    #[test]
+    fn falls_back_to_the_next_font() {
+        let chain = Fonts::default().caption_chain(["Hi שלום"], []).unwrap();
+        let faces = chain
+            .iter()
+            .map(LoadedFont::face)
+            .collect::<Result<Vec<Face>, _>>()
+            .unwrap();
+
+        assert_eq!(chain[0].source, FontSource::Roboto);
+        assert!(faces.len() > 1);
+
+        let runs = split_runs(&faces, "Hi שלום!", 10.0);
+        let texts = runs
+            .iter()
+            .map(|run| (run.font, run.text.as_str()))
+            .collect::<Vec<_>>();
+
+        // The space and the exclamation mark stay with the font before them
+        assert_eq!(texts[0], (0, "Hi "));
+        assert_eq!(texts[1].1, "שלום!");
+        assert!(texts[1].0 > 0);
+        assert_eq!(runs.len(), 2);
+        assert!(runs.iter().all(|run| run.width > 0.0));
+    }
+
+    #[test]
+    fn lays_lines_out_within_the_width() {
+        let chain = Fonts::default()
+            .caption_chain(["Анна Каренина"], [])
+            .unwrap();
+        let faces = chain
+            .iter()
+            .map(LoadedFont::face)
+            .collect::<Result<Vec<Face>, _>>()
+            .unwrap();
+        let one_line = layout_text(&faces, "Анна Каренина", 10.0, 1000.0);
+        let narrow = layout_text(&faces, "Анна Каренина", 10.0, one_line[0].width - 1.0);
+
+        assert_eq!(one_line.len(), 1);
+        assert_eq!(narrow.len(), 2);
+        assert_eq!(narrow[0].runs[0].text, "Анна");
+        assert_eq!(layout_text(&faces, "a\nb", 10.0, 1000.0).len(), 2);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::normalize::Substitution;
+
+/// A digit appended to a short name: the sum of the two digits before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PaddingStep {
+    pub left: u16,
+    pub right: u16,
+    pub sum: u16,
+}
+
+/// Every step [`calculate_mandala_with_trace`](crate::calculate_mandala_with_trace)
+/// took to get from the text to the seed line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Trace {
+    /// The text after normalisation, when it differs from the input.
+    pub normalized: Option<String>,
+    /// Letters replaced by the folding options.
+    pub substitutions: Vec<Substitution>,
+    /// Recognised letters, digits and Roman numerals as typed, with their
+    /// values.
+    pub letters: Vec<(String, u16)>,
+    /// Digits added to names shorter than the seed width.
+    pub padding: Vec<PaddingStep>,
+    /// Rows folded pairwise until the seed width was reached, starting with
+    /// the letter values and ending with the seed. Empty when no folding
+    /// was needed.
+    pub reductions: Vec<Vec<u16>>,
+}
@@ -0,0 +1,358 @@
+use std::fmt;
+
+const RUSSIAN: [char; 33] = [
+    'а', 'б', 'в', 'г', 'д', 'е', 'ё', 'ж', 'з', 'и', 'й', 'к', 'л', 'м', 'н', 'о', 'п', 'р', 'с',
+    'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', 'я',
+];
+
+// Latin spellings of `RUSSIAN`, letter by letter
+const ISO_9: [&str; 33] = [
+    "a", "b", "v", "g", "d", "e", "ë", "ž", "z", "i", "j", "k", "l", "m", "n", "o", "p", "r", "s",
+    "t", "u", "f", "h", "c", "č", "š", "ŝ", "ʺ", "y", "ʹ", "è", "û", "â",
+];
+
+const GOST_B: [&str; 33] = [
+    "a", "b", "v", "g", "d", "e", "yo", "zh", "z", "i", "j", "k", "l", "m", "n", "o", "p", "r",
+    "s", "t", "u", "f", "x", "cz", "ch", "sh", "shh", "``", "y`", "`", "e`", "yu", "ya",
+];
+
+const ICAO: [&str; 33] = [
+    "a", "b", "v", "g", "d", "e", "e", "zh", "z", "i", "i", "k", "l", "m", "n", "o", "p", "r", "s",
+    "t", "u", "f", "kh", "ts", "ch", "sh", "shch", "ie", "y", "", "e", "iu", "ia",
+];
+
+const PASSPORT_1997: [&str; 33] = [
+    "a", "b", "v", "g", "d", "e", "e", "zh", "z", "i", "y", "k", "l", "m", "n", "o", "p", "r", "s",
+    "t", "u", "f", "kh", "ts", "ch", "sh", "shch", "", "y", "", "e", "yu", "ya",
+];
+
+// Everyday spellings read back whatever the scheme, after its own ones
+const COMMON: [(&str, char); 10] = [
+    ("shch", 'щ'),
+    ("kh", 'х'),
+    ("ts", 'ц'),
+    ("zh", 'ж'),
+    ("ch", 'ч'),
+    ("sh", 'ш'),
+    ("ya", 'я'),
+    ("yu", 'ю'),
+    ("yo", 'ё'),
+    ("ye", 'е'),
+];
+
+/// A romanisation of the Russian alphabet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TransliterationScheme {
+    /// ISO 9, identical to GOST 7.79 system A: one Latin letter with
+    /// diacritics per Cyrillic letter, reversible.
+    Iso9,
+    /// GOST 7.79 system B, ASCII only.
+    GostB,
+    /// ICAO Doc 9303, used in Russian passports since 2014.
+    #[default]
+    Icao,
+    /// The scheme of Russian passports issued from 1997 to 2010.
+    Passport1997,
+}
+
+impl TransliterationScheme {
+    pub const ALL: [TransliterationScheme; 4] = [
+        TransliterationScheme::Iso9,
+        TransliterationScheme::GostB,
+        TransliterationScheme::Icao,
+        TransliterationScheme::Passport1997,
+    ];
+
+    fn table(&self) -> &'static [&'static str; 33] {
+        match self {
+            TransliterationScheme::Iso9 => &ISO_9,
+            TransliterationScheme::GostB => &GOST_B,
+            TransliterationScheme::Icao => &ICAO,
+            TransliterationScheme::Passport1997 => &PASSPORT_1997,
+        }
+    }
+
+    fn latin(&self, letter: char) -> Option<&'static str> {
+        RUSSIAN
+            .iter()
+            .position(|&symbol| symbol == letter)
+            .map(|index| self.table()[index])
+    }
+
+    // Latin spellings to read back, longest first. Where several letters
+    // share a spelling the earlier one in the alphabet wins, and signs
+    // spelled with plain letters (ICAO `ie`) are not guessed at all. The
+    // common spellings fill in what the scheme leaves free.
+    fn reverse(&self) -> Vec<(Vec<char>, char)> {
+        let mut pairs = RUSSIAN
+            .iter()
+            .zip(self.table())
+            .filter(|&(&letter, latin)| {
+                let plain_sign = matches!(letter, 'ъ' | 'ь')
+                    && latin.chars().all(|symbol| symbol.is_ascii_alphabetic());
+
+                !latin.is_empty() && !plain_sign
+            })
+            .map(|(&letter, latin)| (latin.chars().collect::<Vec<char>>(), letter))
+            .collect::<Vec<_>>();
+
+        if *self == TransliterationScheme::GostB {
+            pairs.push((vec!['c'], 'ц'));
+        }
+
+        pairs.extend(
+            COMMON
+                .iter()
+                .map(|&(latin, letter)| (latin.chars().collect(), letter)),
+        );
+
+        let mut reverse: Vec<(Vec<char>, char)> = Vec::new();
+
+        for (latin, letter) in pairs {
+            if !reverse.iter().any(|(known, _)| *known == latin) {
+                reverse.push((latin, letter));
+            }
+        }
+
+        reverse.sort_by_key(|(latin, _)| std::cmp::Reverse(latin.len()));
+        reverse
+    }
+}
+
+impl fmt::Display for TransliterationScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TransliterationScheme::Iso9 => "ISO 9 (ГОСТ 7.79, система А)",
+            TransliterationScheme::GostB => "ГОСТ 7.79, система Б",
+            TransliterationScheme::Icao => "Загранпаспорт (ICAO)",
+            TransliterationScheme::Passport1997 => "Загранпаспорт (1997–2010)",
+        })
+    }
+}
+
+fn lowercase(symbol: char) -> char {
+    symbol.to_lowercase().next().unwrap()
+}
+
+/// Spells the Russian letters of `text` in Latin, other characters are
+/// kept.
+pub fn to_latin(text: &str, scheme: TransliterationScheme) -> String {
+    let chars = text.chars().collect::<Vec<char>>();
+    let mut latin = String::new();
+
+    for (index, &symbol) in chars.iter().enumerate() {
+        let letter = lowercase(symbol);
+        let next = chars.get(index + 1).copied();
+        let previous = index.checked_sub(1).map(|previous| chars[previous]);
+
+        let Some(mut spelling) = scheme.latin(letter) else {
+            latin.push(symbol);
+            continue;
+        };
+
+        // GOST system B writes `ц` as `c` before the front vowels
+        if scheme == TransliterationScheme::GostB
+            && letter == 'ц'
+            && next.is_some_and(|next| matches!(lowercase(next), 'е' | 'и' | 'ы' | 'й'))
+        {
+            spelling = "c";
+        }
+
+        if !symbol.is_uppercase() {
+            latin.push_str(spelling);
+        } else if next.or(previous).is_some_and(char::is_uppercase) {
+            latin.push_str(&spelling.to_uppercase());
+        } else {
+            let mut spelling = spelling.chars();
+
+            latin.extend(spelling.next().into_iter().flat_map(char::to_uppercase));
+            latin.extend(spelling);
+        }
+    }
+
+    latin
+}
+
+fn is_latin_consonant(symbol: char) -> bool {
+    symbol.is_ascii_alphabetic() && !matches!(symbol, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+/// Reads `text` written in `scheme` back into Cyrillic. Spellings shared by
+/// several letters resolve to the most common one, so the result may differ
+/// from the original Russian spelling.
+///
+/// Common spellings outside the scheme are read as well: `kh`, `ts`, `ya`,
+/// `yu` and the like. As in everyday use, `ya` and `ye` after a consonant
+/// stand for a soft sign and the vowel (`Natalya`, `Vasilyev`), and `ia`
+/// ending a word for `ия` (`Maria`).
+pub fn to_cyrillic(text: &str, scheme: TransliterationScheme) -> String {
+    let chars = text.chars().collect::<Vec<char>>();
+    let lower = chars
+        .iter()
+        .map(|&symbol| lowercase(symbol))
+        .collect::<Vec<char>>();
+    let reverse = scheme.reverse();
+    let mut cyrillic = String::new();
+    let mut index = 0;
+
+    let push = |cyrillic: &mut String, letter: char, source: char| {
+        if source.is_uppercase() {
+            cyrillic.extend(letter.to_uppercase());
+        } else {
+            cyrillic.push(letter);
+        }
+    };
+
+    while index < chars.len() {
+        let previous = index.checked_sub(1).map(|previous| lower[previous]);
+        let next = lower.get(index + 1).copied();
+        let word_end = !lower
+            .get(index + 2)
+            .is_some_and(|symbol| symbol.is_alphabetic());
+        let pair = match (lower[index], next) {
+            ('y', Some(vowel @ ('a' | 'e'))) if previous.is_some_and(is_latin_consonant) => {
+                Some(['ь', if vowel == 'a' { 'я' } else { 'е' }])
+            }
+            ('i', Some('a'))
+                if word_end
+                    && previous
+                        .is_some_and(|previous| previous.is_alphabetic() && previous != 'i') =>
+            {
+                Some(['и', 'я'])
+            }
+            _ => None,
+        };
+
+        if let Some(pair) = pair {
+            push(&mut cyrillic, pair[0], chars[index]);
+            push(&mut cyrillic, pair[1], chars[index + 1]);
+            index += 2;
+            continue;
+        }
+
+        match reverse
+            .iter()
+            .find(|(latin, _)| lower[index..].starts_with(latin))
+        {
+            Some((latin, letter)) => {
+                push(&mut cyrillic, *letter, chars[index]);
+                index += latin.len();
+            }
+            None => {
+                cyrillic.push(chars[index]);
+                index += 1;
+            }
+        }
+    }
+
+    cyrillic
+}
+
+/// Converts `text` into the other script: mostly Cyrillic text is spelled
+/// in Latin, anything else is read back into Cyrillic.
+pub fn transliterate(text: &str, scheme: TransliterationScheme) -> String {
+    let cyrillic = text
+        .chars()
+        .filter(|&symbol| RUSSIAN.contains(&lowercase(symbol)))
+        .count();
+    let latin = text
+        .chars()
+        .filter(|symbol| symbol.is_alphabetic() && !RUSSIAN.contains(&lowercase(*symbol)))
+        .count();
+
+    if cyrillic > latin {
+        to_latin(text, scheme)
+    } else {
+        to_cyrillic(text, scheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_everyday_spellings_in_every_scheme() {
+        for scheme in TransliterationScheme::ALL {
+            assert_eq!(to_cyrillic("Natalya", scheme), "Наталья", "{:?}", scheme);
+            assert_eq!(to_cyrillic("Natalia", scheme), "Наталия", "{:?}", scheme);
+            assert_eq!(
+                to_cyrillic("Aleksandr", scheme),
+                "Александр",
+                "{:?}",
+                scheme
+            );
+        }
+    }
+
+    #[test]
+    fn reads_common_digraphs() {
+        for scheme in TransliterationScheme::ALL {
+            assert_eq!(to_cyrillic("Mikhail", scheme), "Михаил", "{:?}", scheme);
+            assert_eq!(to_cyrillic("Yulia", scheme), "Юлия", "{:?}", scheme);
+            assert_eq!(to_cyrillic("Fyodor", scheme), "Фёдор", "{:?}", scheme);
+            assert_eq!(to_cyrillic("Vasilyev", scheme), "Васильев", "{:?}", scheme);
+            assert_eq!(to_cyrillic("NATALYA", scheme), "НАТАЛЬЯ", "{:?}", scheme);
+        }
+    }
+
+    #[test]
+    fn iso_9_round_trips() {
+        let text = "Съешь же ещё этих мягких французских булок, да выпей чаю";
+
+        assert_eq!(
+            to_cyrillic(
+                &to_latin(text, TransliterationScheme::Iso9),
+                TransliterationScheme::Iso9
+            ),
+            text
+        );
+    }
+
+    #[test]
+    fn round_trips_names() {
+        for scheme in TransliterationScheme::ALL {
+            for name in ["Анна Каренина", "Михаил Шолохов", "Борис Пастернак"]
+            {
+                let latin = transliterate(name, scheme);
+
+                assert_eq!(
+                    transliterate(&latin, scheme),
+                    name,
+                    "{:?} {}",
+                    scheme,
+                    latin
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_yo_and_short_i_where_the_scheme_keeps_them() {
+        for scheme in [TransliterationScheme::Iso9, TransliterationScheme::GostB] {
+            for name in ["Пётр Чайковский", "Фёдор Достоевский"] {
+                let latin = transliterate(name, scheme);
+
+                assert_eq!(
+                    transliterate(&latin, scheme),
+                    name,
+                    "{:?} {}",
+                    scheme,
+                    latin
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn spells_icao() {
+        assert_eq!(
+            to_latin("Наталья Щукина", TransliterationScheme::Icao),
+            "Natalia Shchukina"
+        );
+        assert_eq!(to_latin("ЮЛИЯ", TransliterationScheme::Icao), "IULIIA");
+        assert_eq!(to_latin("Я", TransliterationScheme::Icao), "Ia");
+    }
+}